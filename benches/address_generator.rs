@@ -0,0 +1,81 @@
+use bip39::Mnemonic;
+use bitcoin::network::constants::Network;
+use bitcoin::util::bip32::DerivationPath;
+use bitcoin::util::bip32::ExtendedPrivKey;
+use bitcoin::util::bip32::ExtendedPubKey;
+use bitcoin::util::key::Secp256k1;
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+use foogold::checker::DerivationTree;
+use foogold::AddressGenerator;
+use std::str::FromStr;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+const DERIVATION_PATHS: [&str; 6] = [
+    "m/44'/0'/0'/0/0",
+    "m/44'/0'/0'/0/1",
+    "m/49'/0'/0'/0/0",
+    "m/49'/0'/0'/0/1",
+    "m/84'/0'/0'/0/0",
+    "m/84'/0'/0'/0/1",
+];
+
+fn derivation_paths() -> Vec<DerivationPath> {
+    DERIVATION_PATHS
+        .iter()
+        .map(|path| DerivationPath::from_str(path).unwrap())
+        .collect()
+}
+
+fn derivation(criterion: &mut Criterion) {
+    let secp256k1 = Secp256k1::new();
+    let paths = derivation_paths();
+    let tree = DerivationTree::new(&paths);
+    let seed = Mnemonic::parse_normalized(MNEMONIC)
+        .unwrap()
+        .to_seed_normalized("");
+    let master_key = ExtendedPrivKey::new_master(Network::Bitcoin, &seed).unwrap();
+
+    let mut group = criterion.benchmark_group("derivation");
+    group.throughput(Throughput::Elements(paths.len() as u64));
+
+    group.bench_function("full_path", |bencher| {
+        bencher.iter(|| {
+            paths
+                .iter()
+                .map(|path| {
+                    let child = master_key.derive_priv(&secp256k1, path).unwrap();
+                    ExtendedPubKey::from_priv(&secp256k1, &child)
+                })
+                .collect::<Vec<ExtendedPubKey>>()
+        })
+    });
+
+    group.bench_function("shared_prefix", |bencher| {
+        bencher.iter(|| tree.derive(&secp256k1, black_box(&master_key)))
+    });
+
+    group.finish();
+}
+
+fn generate(criterion: &mut Criterion) {
+    let address_generator = AddressGenerator::new(derivation_paths());
+    let mnemonic = Mnemonic::parse_normalized(MNEMONIC).unwrap();
+
+    let mut group = criterion.benchmark_group("address_generator");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("generate", |bencher| {
+        bencher.iter(|| address_generator.generate(black_box(mnemonic.clone())))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, derivation, generate);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+CREATE TABLE shard_progress(
+   search TEXT NOT NULL,
+   shard_index INTEGER NOT NULL,
+   shard_count INTEGER NOT NULL,
+   checked BIGINT NOT NULL,
+   total BIGINT NOT NULL,
+   updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+   PRIMARY KEY(search, shard_index, shard_count)
+);
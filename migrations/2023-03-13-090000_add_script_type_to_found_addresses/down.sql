@@ -0,0 +1 @@
+ALTER TABLE found_addresses DROP COLUMN script_type;
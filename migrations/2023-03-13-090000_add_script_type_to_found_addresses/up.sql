@@ -0,0 +1 @@
+ALTER TABLE found_addresses ADD COLUMN script_type TEXT;
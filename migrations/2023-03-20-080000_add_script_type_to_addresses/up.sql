@@ -0,0 +1,2 @@
+-- only set for addresses that are not encoded in outputs directly, e.g. p2pk
+ALTER TABLE addresses ADD COLUMN script_type TEXT;
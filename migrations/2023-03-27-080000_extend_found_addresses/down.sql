@@ -0,0 +1,8 @@
+ALTER TABLE found_addresses DROP COLUMN acknowledged_at;
+ALTER TABLE found_addresses DROP COLUMN source;
+ALTER TABLE found_addresses DROP COLUMN found_at;
+
+ALTER TABLE found_addresses ALTER COLUMN address DROP NOT NULL;
+ALTER TABLE found_addresses ALTER COLUMN derivation_path DROP NOT NULL;
+ALTER TABLE found_addresses ALTER COLUMN mnemonic DROP NOT NULL;
+ALTER TABLE found_addresses ALTER COLUMN script_type DROP NOT NULL;
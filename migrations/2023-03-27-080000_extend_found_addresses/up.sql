@@ -0,0 +1,21 @@
+UPDATE found_addresses SET script_type = 'unknown' WHERE script_type IS NULL;
+-- rows with a missing address or mnemonic can still be worth something, they are never dropped
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM found_addresses
+        WHERE address IS NULL OR derivation_path IS NULL OR mnemonic IS NULL
+    ) THEN
+        RAISE EXCEPTION 'found_addresses has rows without an address, derivation path or mnemonic. Back them up and fill in the missing values before running this migration';
+    END IF;
+END $$;
+
+ALTER TABLE found_addresses ALTER COLUMN address SET NOT NULL;
+ALTER TABLE found_addresses ALTER COLUMN derivation_path SET NOT NULL;
+ALTER TABLE found_addresses ALTER COLUMN mnemonic SET NOT NULL;
+ALTER TABLE found_addresses ALTER COLUMN script_type SET NOT NULL;
+
+ALTER TABLE found_addresses ADD COLUMN found_at TIMESTAMP NOT NULL DEFAULT NOW();
+-- name of the checker run that found the address
+ALTER TABLE found_addresses ADD COLUMN source TEXT NOT NULL DEFAULT '';
+ALTER TABLE found_addresses ADD COLUMN acknowledged_at TIMESTAMP;
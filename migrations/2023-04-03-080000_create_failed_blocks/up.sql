@@ -0,0 +1,6 @@
+CREATE TABLE failed_blocks(
+   block_number INTEGER PRIMARY KEY,
+   error TEXT NOT NULL,
+   attempts INTEGER NOT NULL DEFAULT 1,
+   failed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,2 @@
+DROP TABLE block_staging;
+DROP TABLE address_staging;
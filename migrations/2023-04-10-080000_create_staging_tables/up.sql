@@ -0,0 +1,9 @@
+-- unindexed tables `index --bulk` copies into, merged into blocks and addresses by `index finish-bulk`
+CREATE UNLOGGED TABLE address_staging(
+   address TEXT NOT NULL,
+   script_type TEXT
+);
+
+CREATE UNLOGGED TABLE block_staging(
+   block_number INTEGER NOT NULL
+);
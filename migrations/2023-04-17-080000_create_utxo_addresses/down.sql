@@ -0,0 +1,2 @@
+DROP TABLE utxo_address_staging;
+DROP TABLE utxo_addresses;
@@ -0,0 +1,10 @@
+-- addresses holding unspent outputs, replaced on every `index import-utxo-snapshot`
+CREATE TABLE utxo_addresses(
+   address TEXT PRIMARY KEY,
+   script_type TEXT
+);
+
+CREATE UNLOGGED TABLE utxo_address_staging(
+   address TEXT NOT NULL,
+   script_type TEXT
+);
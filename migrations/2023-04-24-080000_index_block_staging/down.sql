@@ -0,0 +1 @@
+DROP INDEX block_staging_block_number;
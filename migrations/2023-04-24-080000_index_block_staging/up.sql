@@ -0,0 +1,2 @@
+-- block lookups check staged blocks, so they aren't fetched again before `index finish-bulk`
+CREATE INDEX block_staging_block_number ON block_staging (block_number);
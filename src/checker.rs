@@ -1,58 +1,238 @@
+use crate::indexer::EncryptionError;
 use crate::indexer::RepoError;
 use crate::FoundAddress;
-use crate::Repo;
-use std::thread;
-use std::time;
+use crate::ShardProgress;
+use bip39::Mnemonic;
+use bitcoin::util::bip32::ExtendedPrivKey;
+use std::io;
 use thiserror::Error;
 
+mod account_key;
 mod addresses;
+mod aez;
+mod aezeed;
+mod aezeed_checker;
+mod audit;
 mod combination_checker;
+mod derivation_tree;
+mod descriptor;
+mod entropy;
+mod file_notifier;
+mod found_output;
+mod matrix_notifier;
 mod mnemonic;
 mod mnemonic_checker;
+mod multisig;
+mod notification_queue;
+mod notifier;
 mod random_checker;
+mod script_type;
+mod secret;
+mod shard;
+mod slip39;
+mod slip39_checker;
+mod smtp_notifier;
+mod target;
 mod telegram_client;
+mod webhook_notifier;
 
+pub use account_key::parse_extended_public_key;
+pub use account_key::AccountKey;
+pub use account_key::AccountKeyError;
 pub use addresses::Address;
 pub use addresses::AddressGenerator;
+pub use aezeed::Aezeed;
+pub use aezeed::AezeedError;
+pub use aezeed_checker::AezeedChecker;
+pub use audit::Audit;
+pub use audit::AuditFinding;
 pub use combination_checker::CombinationChecker;
+pub use derivation_tree::DerivationTree;
+pub use descriptor::Descriptor;
+pub use descriptor::DescriptorError;
+pub use entropy::EntropyError;
+pub use entropy::EntropyPattern;
+pub use file_notifier::FileNotifier;
+pub use found_output::FoundOutput;
+pub use matrix_notifier::MatrixNotifier;
 pub use mnemonic::MnemonicGenerator;
 pub use mnemonic_checker::MnemonicChecker;
+pub use mnemonic_checker::MnemonicInput;
+pub use multisig::Multisig;
+pub use multisig::MultisigError;
+pub use multisig::MultisigScriptType;
+pub use notification_queue::NotificationQueue;
+pub use notifier::Notification;
+pub use notifier::NotificationKind;
+pub use notifier::Notifier;
+pub use notifier::NotifierError;
+pub use notifier::Notifiers;
 pub use random_checker::RandomChecker;
+pub use script_type::ScriptType;
+pub use secret::set_log_secrets;
+pub use secret::Redacted;
+pub use secret::Secret;
+pub use shard::Shard;
+pub use shard::ShardError;
+pub use slip39::Slip39Error;
+pub use slip39::Slip39Share;
+pub use slip39::Slip39Wordlist;
+pub use slip39_checker::Slip39Checker;
+pub use smtp_notifier::SmtpNotifier;
+pub use target::Target;
 pub use telegram_client::TelegramClient;
+pub use webhook_notifier::WebhookNotifier;
+
+/// Script type recorded for a matched account key, the address is the key itself
+const ACCOUNT_KEY_SCRIPT_TYPE: &str = "account-key";
+/// Number of checked candidates between progress reports of enumerating checkers
+const PROGRESS_INTERVAL: usize = 1_000;
 
 #[derive(Error, Debug)]
 pub enum CheckerError {
     #[error("Repo Error {0:?}")]
     RepoError(#[from] RepoError),
 
-    #[error("Telegram Error {0:?}")]
-    TelegramError(#[from] frankenstein::Error),
+    #[error("SLIP-39 Error {0:?}")]
+    Slip39Error(#[from] Slip39Error),
+    #[error("Aezeed Error {0:?}")]
+    AezeedError(#[from] AezeedError),
+    #[error("Encryption Error {0:?}")]
+    EncryptionError(#[from] EncryptionError),
+    #[error("IO Error {0:?}")]
+    IoError(#[from] io::Error),
+    #[error("Search {search} has progress of {recorded} shards, use --shard-count {recorded} or another search name")]
+    ShardCountMismatch { search: String, recorded: i32 },
+}
+
+pub fn check_candidate(
+    target: &Target,
+    address_generator: &AddressGenerator,
+    mnemonic: Mnemonic,
+    notifier: &dyn Notifier,
+) -> Result<(), CheckerError> {
+    let master_key = AddressGenerator::master_key(&mnemonic);
+
+    check_master_key(
+        target,
+        address_generator,
+        &master_key,
+        Secret::from(&mnemonic).expose(),
+        notifier,
+    )
+}
+
+/// Checks a master key restored from `secret`, e.g. a mnemonic or a set of SLIP-39 shares
+pub fn check_master_key(
+    target: &Target,
+    address_generator: &AddressGenerator,
+    master_key: &ExtendedPrivKey,
+    secret: &str,
+    notifier: &dyn Notifier,
+) -> Result<(), CheckerError> {
+    if let Target::AccountKey(account_key, _) = target {
+        let key = address_generator.account_key(master_key, account_key.derivation_path());
+
+        if account_key.matches(&key) {
+            let found_account_key = FoundAddress::builder()
+                .address(account_key.key().to_string())
+                .mnemonic(secret.to_string())
+                .derivation_path(account_key.derivation_path().to_string())
+                .script_type(ACCOUNT_KEY_SCRIPT_TYPE.to_string())
+                .build();
+
+            target.record_found(found_account_key)?;
+
+            log::info!(
+                "Found account key {account_key} for secret {}",
+                Redacted(secret)
+            );
+
+            queue_notification(notifier, &Notification::found_account_key(account_key));
+        }
+
+        return Ok(());
+    }
+
+    for address in address_generator.generate_from_master_key(master_key, secret) {
+        check_address(target, &address, notifier)?;
+    }
+
+    Ok(())
 }
 
 pub fn check_address(
-    repo: &Repo,
+    target: &Target,
     address: &Address,
-    telegram_client: &Option<TelegramClient>,
+    notifier: &dyn Notifier,
 ) -> Result<(), CheckerError> {
-    if repo.address_exists(&address.address)? {
+    if target.contains(&address.address)? {
         let found_address = FoundAddress::builder()
             .address(address.address.clone())
-            .mnemonic(address.mnemonic.to_string())
+            .mnemonic(address.mnemonic.expose().to_string())
             .derivation_path(address.derivation_path.to_string())
+            .script_type(address.script_type.to_string())
             .build();
 
-        repo.insert_found_address(found_address)?;
+        target.record_found(found_address)?;
 
         log::info!("Found address {address:?}");
 
-        if let Some(telegram_client) = telegram_client {
-            telegram_client
-                .send_notification(format!("Found a new address {}", address.address))?;
+        queue_notification(notifier, &Notification::found_address(address));
+    }
+
+    Ok(())
+}
+
+/// Notifier failures are only logged, they must never stop the search
+fn queue_notification(notifier: &dyn Notifier, notification: &Notification) {
+    if let Err(error) = notifier.notify(notification) {
+        log::error!("Failed to queue a notification - {error:?}");
+    }
+}
+
+/// Shard progress is summed per search, so all shards of a search must have the same count
+pub fn check_shard_count(target: &Target, search: &str, shard: &Shard) -> Result<(), CheckerError> {
+    if let Some(repo) = target.repo() {
+        let mismatch = repo
+            .shard_progress(search)?
+            .into_iter()
+            .find(|progress| progress.shard_count != shard.count() as i32);
+
+        if let Some(progress) = mismatch {
+            return Err(CheckerError::ShardCountMismatch {
+                search: search.to_string(),
+                recorded: progress.shard_count,
+            });
         }
+    }
+
+    Ok(())
+}
+
+pub fn report_progress(
+    target: &Target,
+    search: &str,
+    shard: &Shard,
+    checked: usize,
+    total: usize,
+) -> Result<(), CheckerError> {
+    log::info!(
+        "Search {search}, shard {}/{}: checked {checked} of {total} candidates",
+        shard.index(),
+        shard.count()
+    );
 
-        let two_secs = time::Duration::from_millis(2_000);
+    if let Some(repo) = target.repo() {
+        let progress = ShardProgress::builder()
+            .search(search)
+            .shard_index(shard.index() as i32)
+            .shard_count(shard.count() as i32)
+            .checked(checked as i64)
+            .total(total as i64)
+            .build();
 
-        thread::sleep(two_secs);
+        repo.upsert_shard_progress(&progress)?;
     }
 
     Ok(())
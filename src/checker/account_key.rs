@@ -0,0 +1,207 @@
+use bitcoin::util::base58;
+use bitcoin::util::bip32::ChildNumber;
+use bitcoin::util::bip32::DerivationPath;
+use bitcoin::util::bip32::Error;
+use bitcoin::util::bip32::ExtendedPubKey;
+use std::hint;
+use thiserror::Error;
+
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const YPUB_VERSION: [u8; 4] = [0x04, 0x9d, 0x7c, 0xb2];
+const ZPUB_VERSION: [u8; 4] = [0x04, 0xb2, 0x47, 0x46];
+const YPUB_MULTISIG_VERSION: [u8; 4] = [0x02, 0x95, 0xb4, 0x3f];
+const ZPUB_MULTISIG_VERSION: [u8; 4] = [0x02, 0xaa, 0x7e, 0xd3];
+
+#[derive(Error, Debug)]
+pub enum AccountKeyError {
+    #[error("Extended Key Error {0:?}")]
+    KeyError(#[from] Error),
+    #[error("Account key depth {depth} doesn't match derivation path {derivation_path}")]
+    DepthMismatch {
+        depth: u8,
+        derivation_path: DerivationPath,
+    },
+}
+
+/// Account level extended public key exported from a wallet, e.g. a watch-only one
+#[derive(Debug, Clone)]
+pub struct AccountKey {
+    key: ExtendedPubKey,
+    derivation_path: DerivationPath,
+}
+
+impl AccountKey {
+    /// Parses `xpub`, `ypub` or `zpub` keys.
+    ///
+    /// If `derivation_path` is not set, the account path is taken from the key version:
+    /// `m/44'/0'/0'` for `xpub`, `m/49'/0'/0'` for `ypub` and `m/84'/0'/0'` for `zpub`.
+    pub fn parse(
+        raw_key: &str,
+        derivation_path: Option<DerivationPath>,
+    ) -> Result<Self, AccountKeyError> {
+        let (version, key) = decode_extended_public_key(raw_key)?;
+
+        let purpose = match version {
+            XPUB_VERSION => 44,
+            YPUB_VERSION => 49,
+            ZPUB_VERSION => 84,
+            version => return Err(Error::UnknownVersion(version).into()),
+        };
+
+        let derivation_path = derivation_path.unwrap_or_else(|| {
+            DerivationPath::from(vec![
+                ChildNumber::Hardened { index: purpose },
+                ChildNumber::Hardened { index: 0 },
+                ChildNumber::Hardened { index: 0 },
+            ])
+        });
+
+        Self::new(key, derivation_path)
+    }
+
+    pub fn new(
+        key: ExtendedPubKey,
+        derivation_path: DerivationPath,
+    ) -> Result<Self, AccountKeyError> {
+        if key.depth as usize != derivation_path.len() {
+            return Err(AccountKeyError::DepthMismatch {
+                depth: key.depth,
+                derivation_path,
+            });
+        }
+
+        Ok(Self {
+            key,
+            derivation_path,
+        })
+    }
+
+    /// The key as an xpub, whatever version it was given with
+    pub fn key(&self) -> &ExtendedPubKey {
+        &self.key
+    }
+
+    pub fn derivation_path(&self) -> &DerivationPath {
+        &self.derivation_path
+    }
+
+    /// Compares the public key and chain code in constant time, like other secret dependent data
+    pub fn matches(&self, key: &ExtendedPubKey) -> bool {
+        constant_time_eq(
+            &self.key.public_key.serialize(),
+            &key.public_key.serialize(),
+        ) & constant_time_eq(self.key.chain_code.as_bytes(), key.chain_code.as_bytes())
+    }
+}
+
+/// Parses `xpub`, `ypub`, `zpub`, `Ypub` and `Zpub` keys
+pub fn parse_extended_public_key(raw_key: &str) -> Result<ExtendedPubKey, Error> {
+    let (_version, key) = decode_extended_public_key(raw_key)?;
+
+    Ok(key)
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    let difference = left
+        .iter()
+        .zip(right)
+        .fold(0, |difference, (left, right)| difference | (left ^ right));
+
+    hint::black_box(difference) == 0
+}
+
+fn decode_extended_public_key(raw_key: &str) -> Result<([u8; 4], ExtendedPubKey), Error> {
+    let mut data = base58::from_check(raw_key)?;
+
+    if data.len() != 78 {
+        return Err(Error::WrongExtendedKeyLength(data.len()));
+    }
+
+    let version = [data[0], data[1], data[2], data[3]];
+
+    match version {
+        XPUB_VERSION
+        | YPUB_VERSION
+        | ZPUB_VERSION
+        | YPUB_MULTISIG_VERSION
+        | ZPUB_MULTISIG_VERSION => (),
+        version => return Err(Error::UnknownVersion(version)),
+    }
+
+    data[0..4].copy_from_slice(&XPUB_VERSION);
+
+    Ok((version, ExtendedPubKey::decode(&data)?))
+}
+
+impl std::fmt::Display for AccountKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({})", self.key, self.derivation_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // account keys of the "abandon ... about" mnemonic from BIP-44, BIP-49 and BIP-84
+    const XPUB: &str = "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj";
+    const YPUB: &str = "ypub6Ww3ibxVfGzLrAH1PNcjyAWenMTbbAosGNB6VvmSEgytSER9azLDWCxoJwW7Ke7icmizBMXrzBx9979FfaHxHcrArf3zbeJJJUZPf663zsP";
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    #[test]
+    fn infers_account_path_from_version() {
+        for (raw_key, path) in [
+            (XPUB, "m/44'/0'/0'"),
+            (YPUB, "m/49'/0'/0'"),
+            (ZPUB, "m/84'/0'/0'"),
+        ] {
+            let account_key = AccountKey::parse(raw_key, None).unwrap();
+
+            assert_eq!(account_key.derivation_path().to_string(), path);
+            assert_eq!(account_key.key().depth, 3);
+        }
+    }
+
+    #[test]
+    fn decodes_key_as_xpub() {
+        let account_key = AccountKey::parse(ZPUB, None).unwrap();
+
+        assert_eq!(
+            account_key.key().to_string(),
+            "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V"
+        );
+        assert_eq!(parse_extended_public_key(ZPUB).unwrap(), *account_key.key());
+    }
+
+    #[test]
+    fn uses_given_account_path() {
+        let path = DerivationPath::from_str("m/84'/0'/7'").unwrap();
+        let account_key = AccountKey::parse(ZPUB, Some(path.clone())).unwrap();
+
+        assert_eq!(*account_key.derivation_path(), path);
+    }
+
+    #[test]
+    fn rejects_path_of_other_depth() {
+        let path = DerivationPath::from_str("m/84'/0'").unwrap();
+
+        assert!(matches!(
+            AccountKey::parse(ZPUB, Some(path)),
+            Err(AccountKeyError::DepthMismatch { depth: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn matches_key_regardless_of_version() {
+        let account_key = AccountKey::parse(ZPUB, None).unwrap();
+
+        assert!(account_key.matches(&parse_extended_public_key(ZPUB).unwrap()));
+        assert!(!account_key.matches(&parse_extended_public_key(XPUB).unwrap()));
+        assert!(!account_key.matches(&parse_extended_public_key(YPUB).unwrap()));
+    }
+}
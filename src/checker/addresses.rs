@@ -1,66 +1,122 @@
+use super::DerivationTree;
+use super::Multisig;
+use super::ScriptType;
+use super::Secret;
 use bip39::Mnemonic;
 use bitcoin::network::constants::Network;
 use bitcoin::secp256k1::All;
-use bitcoin::util::address::Address as BitcoinAddress;
-use bitcoin::util::bip32::ChildNumber;
 use bitcoin::util::bip32::DerivationPath;
 use bitcoin::util::bip32::ExtendedPrivKey;
 use bitcoin::util::bip32::ExtendedPubKey;
-use bitcoin::util::key::PublicKey;
 use bitcoin::util::key::Secp256k1;
 use typed_builder::TypedBuilder;
 
 pub struct AddressGenerator {
     derivation_paths: Vec<DerivationPath>,
+    script_types: Vec<ScriptType>,
+    derivation_tree: DerivationTree,
     secp256k1: Secp256k1<All>,
+    multisig: Option<Multisig>,
 }
 
 #[derive(TypedBuilder, Debug)]
 pub struct Address {
-    pub mnemonic: String,
+    pub mnemonic: Secret,
     pub derivation_path: DerivationPath,
+    pub script_type: ScriptType,
     pub address: String,
 }
 
 impl AddressGenerator {
+    /// Script types are taken from the purpose of derivation paths, e.g. `84'` for P2WPKH
     pub fn new(derivation_paths: Vec<DerivationPath>) -> AddressGenerator {
-        if derivation_paths.is_empty() {
+        let paths = derivation_paths
+            .into_iter()
+            .map(|path| match ScriptType::from_derivation_path(&path) {
+                Some(script_type) => (path, script_type),
+                None => panic!("Invalid derivation path {path}"),
+            })
+            .collect();
+
+        Self::with_script_types(paths)
+    }
+
+    pub fn with_script_types(paths: Vec<(DerivationPath, ScriptType)>) -> AddressGenerator {
+        if paths.is_empty() {
             panic!("Derivation paths vector is empty!")
         };
 
+        Self::from_paths(paths)
+    }
+
+    /// Generator without addresses for account key targets, only [`Self::account_key`] is used
+    pub fn account_keys_only() -> AddressGenerator {
+        Self::from_paths(vec![])
+    }
+
+    fn from_paths(paths: Vec<(DerivationPath, ScriptType)>) -> AddressGenerator {
+        let (derivation_paths, script_types): (Vec<DerivationPath>, Vec<ScriptType>) =
+            paths.into_iter().unzip();
+
         let secp256k1 = Secp256k1::new();
+        let derivation_tree = DerivationTree::new(&derivation_paths);
+
         Self {
             derivation_paths,
+            script_types,
+            derivation_tree,
             secp256k1,
+            multisig: None,
+        }
+    }
+
+    /// Generates multisig addresses where the checked mnemonic provides one of the keys
+    pub fn multisig(multisig: Multisig) -> AddressGenerator {
+        let script_type = ScriptType::Multisig(multisig.script_type());
+        let paths = multisig
+            .derivation_paths()
+            .iter()
+            .map(|path| (path.clone(), script_type))
+            .collect();
+
+        Self {
+            multisig: Some(multisig),
+            ..Self::with_script_types(paths)
         }
     }
 
     pub fn generate(&self, mnemonic: Mnemonic) -> Vec<Address> {
-        let seed = mnemonic.to_seed_normalized("");
-        let master_key = ExtendedPrivKey::new_master(Network::Bitcoin, &seed).unwrap();
+        let master_key = Self::master_key(&mnemonic);
+
+        self.generate_from_master_key(&master_key, Secret::from(&mnemonic).expose())
+    }
+
+    /// Generates addresses of a master key, `secret` is the backup it was restored from
+    pub fn generate_from_master_key(
+        &self,
+        master_key: &ExtendedPrivKey,
+        secret: &str,
+    ) -> Vec<Address> {
+        let public_keys = self.derivation_tree.derive(&self.secp256k1, master_key);
 
         let mut addresses: Vec<Address> = vec![];
 
-        for path in &self.derivation_paths {
-            let child = master_key.derive_priv(&self.secp256k1, path).unwrap();
-            let public_key = ExtendedPubKey::from_priv(&self.secp256k1, &child).public_key;
-
-            let addr = match path.into_iter().next() {
-                Some(ChildNumber::Hardened { index: 84 }) => {
-                    BitcoinAddress::p2wpkh(&PublicKey::new(public_key), Network::Bitcoin).unwrap()
-                }
-                Some(ChildNumber::Hardened { index: 49 }) => {
-                    BitcoinAddress::p2shwpkh(&PublicKey::new(public_key), Network::Bitcoin).unwrap()
-                }
-                Some(ChildNumber::Hardened { index: 44 }) => {
-                    BitcoinAddress::p2pkh(&PublicKey::new(public_key), Network::Bitcoin)
-                }
-                value => panic!("Invalid derivation path {value:?}"),
+        for (position, ((path, script_type), extended_public_key)) in self
+            .derivation_paths
+            .iter()
+            .zip(&self.script_types)
+            .zip(public_keys)
+            .enumerate()
+        {
+            let addr = match &self.multisig {
+                Some(multisig) => multisig.address(position, extended_public_key.public_key),
+                None => script_type.address(&self.secp256k1, extended_public_key.public_key),
             };
 
             let address = Address::builder()
-                .mnemonic(mnemonic.to_string())
+                .mnemonic(Secret::new(secret))
                 .derivation_path(path.clone())
+                .script_type(*script_type)
                 .address(addr.to_string())
                 .build();
 
@@ -69,4 +125,27 @@ impl AddressGenerator {
 
         addresses
     }
+
+    /// Derives the extended public key of an account, e.g. `m/84'/0'/0'`
+    pub fn account_key(
+        &self,
+        master_key: &ExtendedPrivKey,
+        derivation_path: &DerivationPath,
+    ) -> ExtendedPubKey {
+        let account_key = master_key
+            .derive_priv(&self.secp256k1, derivation_path)
+            .unwrap();
+
+        ExtendedPubKey::from_priv(&self.secp256k1, &account_key)
+    }
+
+    pub fn master_key(mnemonic: &Mnemonic) -> ExtendedPrivKey {
+        let seed = mnemonic.to_seed_normalized("");
+
+        Self::master_key_from_seed(&seed)
+    }
+
+    pub fn master_key_from_seed(seed: &[u8]) -> ExtendedPrivKey {
+        ExtendedPrivKey::new_master(Network::Bitcoin, seed).unwrap()
+    }
 }
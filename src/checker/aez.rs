@@ -0,0 +1,308 @@
+use aes::hazmat::cipher_round;
+use blake2::digest::Update;
+use blake2::digest::VariableOutput;
+use blake2::Blake2bVar;
+
+const BLOCK_SIZE: usize = 16;
+const EXTRACTED_KEY_SIZE: usize = 48;
+const MAX_TINY_SIZE: usize = 32;
+
+type Block = [u8; BLOCK_SIZE];
+
+/// AEZ v5 decryption as used by aezeed.
+///
+/// Only ciphertexts shorter than 32 bytes are supported, they are enciphered with AEZ-tiny.
+pub struct Aez {
+    i: Block,
+    j: Block,
+    l: Block,
+}
+
+impl Aez {
+    pub fn new(key: &[u8]) -> Self {
+        let mut extracted_key = [0; EXTRACTED_KEY_SIZE];
+
+        if key.len() == EXTRACTED_KEY_SIZE {
+            extracted_key.copy_from_slice(key);
+        } else {
+            let mut hasher = Blake2bVar::new(EXTRACTED_KEY_SIZE).unwrap();
+            hasher.update(key);
+            hasher.finalize_variable(&mut extracted_key).unwrap();
+        }
+
+        let block = |position: usize| -> Block {
+            extracted_key[position * BLOCK_SIZE..(position + 1) * BLOCK_SIZE]
+                .try_into()
+                .unwrap()
+        };
+
+        Self {
+            i: block(0),
+            j: block(1),
+            l: block(2),
+        }
+    }
+
+    /// Returns the plaintext, or `None` if the ciphertext is not authentic
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        additional_data: &[&[u8]],
+        tau: usize,
+        ciphertext: &[u8],
+    ) -> Option<Vec<u8>> {
+        if ciphertext.len() <= tau || ciphertext.len() >= MAX_TINY_SIZE {
+            panic!(
+                "AEZ ciphertexts from {} to {} bytes are supported, got {}",
+                tau + 1,
+                MAX_TINY_SIZE - 1,
+                ciphertext.len()
+            );
+        }
+
+        let delta = self.hash(nonce, additional_data, tau * 8);
+        let mut plaintext = self.tiny(&delta, ciphertext, true);
+        let tag = plaintext.split_off(ciphertext.len() - tau);
+
+        if tag.iter().all(|byte| *byte == 0) {
+            Some(plaintext)
+        } else {
+            None
+        }
+    }
+
+    fn hash(&self, nonce: &[u8], additional_data: &[&[u8]], tau_bits: usize) -> Block {
+        let mut tau_block = [0; BLOCK_SIZE];
+        tau_block[12..].copy_from_slice(&(tau_bits as u32).to_be_bytes());
+
+        let mut sum = self.e(3, 1, &tau_block);
+
+        xor_into(&mut sum, &self.hash_item(4, nonce));
+
+        for (position, item) in additional_data.iter().enumerate() {
+            xor_into(&mut sum, &self.hash_item(5 + position as u32, item));
+        }
+
+        sum
+    }
+
+    fn hash_item(&self, j: u32, data: &[u8]) -> Block {
+        let mut sum = [0; BLOCK_SIZE];
+        let chunks = data.chunks_exact(BLOCK_SIZE);
+        let remainder = chunks.remainder();
+
+        for (position, chunk) in chunks.enumerate() {
+            xor_into(
+                &mut sum,
+                &self.e(j, position as u32 + 1, chunk.try_into().unwrap()),
+            );
+        }
+
+        if !remainder.is_empty() || data.is_empty() {
+            let mut padded = [0; BLOCK_SIZE];
+            padded[..remainder.len()].copy_from_slice(remainder);
+            padded[remainder.len()] = 0x80;
+
+            xor_into(&mut sum, &self.e(j, 0, &padded));
+        }
+
+        sum
+    }
+
+    fn tiny(&self, delta: &Block, input: &[u8], decipher: bool) -> Vec<u8> {
+        let size = input.len();
+        let half = size.div_ceil(2);
+        let middle = size / 2;
+        let odd = size % 2 == 1;
+
+        let (i, rounds) = match size {
+            1 => (7, 24),
+            2 => (7, 16),
+            size if size < BLOCK_SIZE => (7, 10),
+            _ => (6, 8),
+        };
+
+        let mut left = [0; BLOCK_SIZE];
+        let mut right = [0; BLOCK_SIZE];
+
+        left[..half].copy_from_slice(&input[..half]);
+        right[..half].copy_from_slice(&input[middle..middle + half]);
+
+        // halves of odd sizes end in a nibble, align the right one to the left
+        let (mask, pad) = if odd {
+            for position in 0..middle {
+                right[position] = (right[position] << 4) | (right[position + 1] >> 4);
+            }
+            right[middle] <<= 4;
+
+            (0xF0, 0x08)
+        } else {
+            (0x00, 0x80)
+        };
+
+        let round_input = |half_block: &Block, round: i32| -> Block {
+            let mut block = [0; BLOCK_SIZE];
+            block[..half].copy_from_slice(&half_block[..half]);
+            block[middle] = (block[middle] & mask) | pad;
+            xor_into(&mut block, delta);
+            block[15] ^= round as u8;
+            block
+        };
+
+        let (mut round, step) = if decipher {
+            if size < BLOCK_SIZE {
+                left[0] ^= self.tiny_mask(delta, input);
+            }
+
+            (rounds - 1, -1)
+        } else {
+            (0, 1)
+        };
+
+        for _ in 0..rounds / 2 {
+            xor_into(&mut left, &self.e(0, i, &round_input(&right, round)));
+            xor_into(&mut right, &self.e(0, i, &round_input(&left, round + step)));
+            round += 2 * step;
+        }
+
+        let mut output = vec![0; size];
+        output[..middle].copy_from_slice(&right[..middle]);
+        output[middle..].copy_from_slice(&left[..half]);
+
+        if odd {
+            for position in (middle + 1..size).rev() {
+                output[position] = (output[position] >> 4) | (output[position - 1] << 4);
+            }
+            output[middle] = (left[0] >> 4) | (right[middle] & 0xF0);
+        }
+
+        if size < BLOCK_SIZE && !decipher {
+            output[0] ^= self.tiny_mask(delta, &output);
+        }
+
+        output
+    }
+
+    /// Mask of the first bit for inputs shorter than a block
+    fn tiny_mask(&self, delta: &Block, input: &[u8]) -> u8 {
+        let mut block = [0; BLOCK_SIZE];
+        block[..input.len()].copy_from_slice(input);
+        block[0] |= 0x80;
+        xor_into(&mut block, delta);
+
+        self.e(0, 3, &block)[0] & 0x80
+    }
+
+    /// Tweakable block cipher `E(j, i)` for `j >= 0`
+    fn e(&self, j: u32, i: u32, input: &Block) -> Block {
+        let mut block = *input;
+
+        xor_into(&mut block, &multiply(j, &self.j));
+        xor_into(&mut block, &multiply(1 << i.div_ceil(8), &self.i));
+        xor_into(&mut block, &multiply(i % 8, &self.l));
+
+        for round_key in [&self.j, &self.i, &self.l, &[0; BLOCK_SIZE]] {
+            cipher_round((&mut block).into(), round_key.into());
+        }
+
+        block
+    }
+}
+
+/// Multiplication by a small integer in GF(2^128)
+fn multiply(factor: u32, block: &Block) -> Block {
+    let mut result = [0; BLOCK_SIZE];
+    let mut power = *block;
+    let mut factor = factor;
+
+    while factor > 0 {
+        if factor & 1 == 1 {
+            xor_into(&mut result, &power);
+        }
+
+        power = double(&power);
+        factor >>= 1;
+    }
+
+    result
+}
+
+fn double(block: &Block) -> Block {
+    let mut result = [0; BLOCK_SIZE];
+
+    for position in 0..BLOCK_SIZE - 1 {
+        result[position] = (block[position] << 1) | (block[position + 1] >> 7);
+    }
+
+    result[BLOCK_SIZE - 1] =
+        (block[BLOCK_SIZE - 1] << 1) ^ if block[0] >> 7 == 1 { 0x87 } else { 0 };
+
+    result
+}
+
+fn xor_into(block: &mut Block, other: &Block) {
+    for (byte, other_byte) in block.iter_mut().zip(other) {
+        *byte ^= other_byte;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::hex::FromHex;
+
+    /// Scrypt key, additional data and ciphertext of the aezeed test vector with the default
+    /// passphrase, AEZ-tiny of 23 bytes with an empty nonce
+    const KEY: &str = "73a7eadcffcd7cc7e98c437686b1fef19e4ccfeaed67a89b0ab0cf22e6a59040";
+    const ADDITIONAL_DATA: &str = "0073616c7431";
+    const CIPHERTEXT: &str = "304b9e6aa12884a7a9ae0abc263fcbe543c58c3cd0890e";
+    const PLAINTEXT: &str = "00000081b637d86359e6960de795e41e0b4cfd";
+
+    fn decrypt(key: &str, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let additional_data = Vec::from_hex(ADDITIONAL_DATA).unwrap();
+
+        Aez::new(&Vec::from_hex(key).unwrap()).decrypt(&[], &[&additional_data], 4, ciphertext)
+    }
+
+    #[test]
+    fn decrypts_aezeed_vector() {
+        let ciphertext = Vec::from_hex(CIPHERTEXT).unwrap();
+
+        assert_eq!(
+            decrypt(KEY, &ciphertext),
+            Some(Vec::from_hex(PLAINTEXT).unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_modified_ciphertext() {
+        let mut ciphertext = Vec::from_hex(CIPHERTEXT).unwrap();
+        ciphertext[11] ^= 0x01;
+
+        assert_eq!(decrypt(KEY, &ciphertext), None);
+    }
+
+    #[test]
+    fn rejects_wrong_key() {
+        let ciphertext = Vec::from_hex(CIPHERTEXT).unwrap();
+        let wrong_key = KEY.replace("73a7", "73a8");
+
+        assert_eq!(decrypt(&wrong_key, &ciphertext), None);
+    }
+
+    #[test]
+    fn multiplies_in_gf128() {
+        let mut block = [0; BLOCK_SIZE];
+        block[0] = 0x80;
+
+        let mut reduced = [0; BLOCK_SIZE];
+        reduced[BLOCK_SIZE - 1] = 0x87;
+
+        let mut tripled = reduced;
+        tripled[0] = 0x80;
+
+        assert_eq!(double(&block), reduced);
+        assert_eq!(multiply(3, &block), tripled);
+        assert_eq!(multiply(0, &block), [0; BLOCK_SIZE]);
+    }
+}
@@ -0,0 +1,293 @@
+use super::aez::Aez;
+use super::AddressGenerator;
+use super::ScriptType;
+use bip39::Language;
+use bitcoin::util::bip32::ChildNumber;
+use bitcoin::util::bip32::DerivationPath;
+use bitcoin::util::bip32::ExtendedPrivKey;
+use thiserror::Error;
+use zeroize::Zeroize;
+use zeroize::ZeroizeOnDrop;
+use zeroize::Zeroizing;
+
+const VERSION: u8 = 0;
+const WORD_COUNT: usize = 24;
+const BITS_PER_WORD: usize = 11;
+const ENCIPHERED_SIZE: usize = 33;
+const DECIPHERED_SIZE: usize = 19;
+const CIPHERTEXT_EXPANSION: usize = 4;
+const SALT_SIZE: usize = 5;
+const CHECKSUM_SIZE: usize = 4;
+const ENTROPY_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+const DEFAULT_PASSPHRASE: &str = "aezeed";
+
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// Accounts of the LND on-chain wallet with the script types of their receive and change
+/// addresses. The nested segwit account of btcwallet uses native segwit change addresses.
+const LND_ACCOUNTS: [(u32, ScriptType, ScriptType); 2] = [
+    (84, ScriptType::P2wpkh, ScriptType::P2wpkh),
+    (49, ScriptType::P2shP2wpkh, ScriptType::P2wpkh),
+];
+/// Purpose of LND key families, `m/1017'/coin'/family'/0/index`
+const LND_KEY_PURPOSE: u32 = 1017;
+/// Key family of the node identity key
+const NODE_KEY_FAMILY: u32 = 6;
+
+#[derive(Error, Debug)]
+pub enum AezeedError {
+    #[error("Invalid mnemonic length {0}, aezeed has {WORD_COUNT} words")]
+    InvalidLength(usize),
+    #[error("Unknown word {0}")]
+    UnknownWord(String),
+    #[error("Unsupported aezeed version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Invalid checksum")]
+    InvalidChecksum,
+    #[error("Invalid passphrase")]
+    InvalidPassphrase,
+}
+
+/// LND cipher seed.
+///
+/// The 24 words (BIP-39 wordlist) encode a version, an AEZ encrypted payload with the wallet
+/// birthday and 16 bytes of entropy, a salt and a CRC-32C checksum. The entropy is used as
+/// the BIP-32 seed of the root key.
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct Aezeed {
+    birthday: u16,
+    entropy: [u8; ENTROPY_SIZE],
+}
+
+impl Aezeed {
+    /// Decodes a mnemonic, an empty passphrase stands for the default one
+    pub fn decode(mnemonic: &str, passphrase: &str) -> Result<Aezeed, AezeedError> {
+        Self::decode_with_scrypt_cost(mnemonic, passphrase, SCRYPT_LOG_N)
+    }
+
+    fn decode_with_scrypt_cost(
+        mnemonic: &str,
+        passphrase: &str,
+        scrypt_log_n: u8,
+    ) -> Result<Aezeed, AezeedError> {
+        let words: Vec<&str> = mnemonic.split_whitespace().collect();
+
+        if words.len() != WORD_COUNT {
+            return Err(AezeedError::InvalidLength(words.len()));
+        }
+
+        let word_list = Language::English.all_words();
+        let mut bits = Vec::with_capacity(WORD_COUNT * BITS_PER_WORD);
+
+        for word in words {
+            let index = word_list
+                .iter()
+                .position(|candidate| *candidate == word.to_lowercase())
+                .ok_or_else(|| AezeedError::UnknownWord(word.to_string()))?;
+
+            bits.extend((0..BITS_PER_WORD).rev().map(|bit| (index >> bit) & 1 == 1));
+        }
+
+        let enciphered: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+            .collect();
+
+        if enciphered[0] != VERSION {
+            return Err(AezeedError::UnsupportedVersion(enciphered[0]));
+        }
+
+        let (payload, checksum) = enciphered.split_at(ENCIPHERED_SIZE - CHECKSUM_SIZE);
+
+        if crc32c::crc32c(payload).to_be_bytes() != checksum {
+            return Err(AezeedError::InvalidChecksum);
+        }
+
+        let salt = &payload[payload.len() - SALT_SIZE..];
+        let ciphertext = &payload[1..payload.len() - SALT_SIZE];
+
+        let passphrase = if passphrase.is_empty() {
+            DEFAULT_PASSPHRASE
+        } else {
+            passphrase
+        };
+
+        let params = scrypt::Params::new(scrypt_log_n, SCRYPT_R, SCRYPT_P).unwrap();
+        let mut key = Zeroizing::new([0; KEY_SIZE]);
+        scrypt::scrypt(passphrase.as_bytes(), salt, &params, key.as_mut()).unwrap();
+
+        let additional_data = [&[VERSION], salt].concat();
+        let deciphered = Aez::new(key.as_ref())
+            .decrypt(&[], &[&additional_data], CIPHERTEXT_EXPANSION, ciphertext)
+            .map(Zeroizing::new)
+            .ok_or(AezeedError::InvalidPassphrase)?;
+
+        debug_assert_eq!(deciphered.len(), DECIPHERED_SIZE);
+
+        if deciphered[0] != VERSION {
+            return Err(AezeedError::UnsupportedVersion(deciphered[0]));
+        }
+
+        Ok(Aezeed {
+            birthday: u16::from_be_bytes([deciphered[1], deciphered[2]]),
+            entropy: deciphered[3..].try_into().unwrap(),
+        })
+    }
+
+    /// Wallet birthday in days since the genesis block
+    pub fn birthday(&self) -> u16 {
+        self.birthday
+    }
+
+    pub fn master_key(&self) -> ExtendedPrivKey {
+        AddressGenerator::master_key_from_seed(&self.entropy)
+    }
+
+    /// Receive and change addresses of the LND on-chain accounts `m/84'/0'/0'` and `m/49'/0'/0'`,
+    /// the change addresses of `m/49'/0'/0'` are P2WPKH
+    pub fn derivation_paths(address_count: u32) -> Vec<(DerivationPath, ScriptType)> {
+        let mut paths = vec![];
+
+        for (purpose, receive_script_type, change_script_type) in LND_ACCOUNTS {
+            for (chain, script_type) in [(0, receive_script_type), (1, change_script_type)] {
+                for index in 0..address_count {
+                    let path = DerivationPath::from(vec![
+                        ChildNumber::Hardened { index: purpose },
+                        ChildNumber::Hardened { index: 0 },
+                        ChildNumber::Hardened { index: 0 },
+                        ChildNumber::Normal { index: chain },
+                        ChildNumber::Normal { index },
+                    ]);
+
+                    paths.push((path, script_type));
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Path of the node identity key, `m/1017'/0'/6'/0/0`
+    pub fn node_key_path() -> DerivationPath {
+        DerivationPath::from(vec![
+            ChildNumber::Hardened {
+                index: LND_KEY_PURPOSE,
+            },
+            ChildNumber::Hardened { index: 0 },
+            ChildNumber::Hardened {
+                index: NODE_KEY_FAMILY,
+            },
+            ChildNumber::Normal { index: 0 },
+            ChildNumber::Normal { index: 0 },
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The vectors of LND's `aezeed` package are enciphered with its test scrypt cost of N = 16
+    const TEST_SCRYPT_LOG_N: u8 = 4;
+    const TEST_ENTROPY: [u8; ENTROPY_SIZE] = [
+        0x81, 0xb6, 0x37, 0xd8, 0x63, 0x59, 0xe6, 0x96, 0x0d, 0xe7, 0x95, 0xe4, 0x1e, 0x0b, 0x4c,
+        0xfd,
+    ];
+    const DEFAULT_PASSPHRASE_MNEMONIC: &str = "ability liquid travel stem barely drastic pact \
+        cupboard apple thrive morning oak feature tissue couch old math inform success suggest \
+        drink motion know royal";
+    const PASSPHRASE_MNEMONIC: &str = "able tree stool crush transfer cloud cross three profit \
+        outside hen citizen plate ride require leg siren drum success suggest drink require \
+        fiscal upgrade";
+    const PASSPHRASE: &str = "!very_safe_55345_password*";
+
+    fn decode(mnemonic: &str, passphrase: &str) -> Result<Aezeed, AezeedError> {
+        Aezeed::decode_with_scrypt_cost(mnemonic, passphrase, TEST_SCRYPT_LOG_N)
+    }
+
+    #[test]
+    fn decodes_mnemonic_with_default_passphrase() {
+        let aezeed = decode(DEFAULT_PASSPHRASE_MNEMONIC, "").unwrap();
+
+        assert_eq!(aezeed.entropy, TEST_ENTROPY);
+        assert_eq!(aezeed.birthday(), 0);
+    }
+
+    #[test]
+    fn decodes_mnemonic_with_passphrase() {
+        let aezeed = decode(PASSPHRASE_MNEMONIC, PASSPHRASE).unwrap();
+
+        assert_eq!(aezeed.entropy, TEST_ENTROPY);
+        assert_eq!(aezeed.birthday(), 3365);
+    }
+
+    #[test]
+    fn rejects_wrong_passphrase() {
+        assert!(matches!(
+            decode(PASSPHRASE_MNEMONIC, ""),
+            Err(AezeedError::InvalidPassphrase)
+        ));
+        assert!(matches!(
+            decode(DEFAULT_PASSPHRASE_MNEMONIC, PASSPHRASE),
+            Err(AezeedError::InvalidPassphrase)
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_mnemonics() {
+        let swapped_word = DEFAULT_PASSPHRASE_MNEMONIC.replace("royal", "february");
+
+        assert!(matches!(
+            decode(&swapped_word, ""),
+            Err(AezeedError::InvalidChecksum)
+        ));
+        assert!(matches!(
+            decode("ability liquid travel", ""),
+            Err(AezeedError::InvalidLength(3))
+        ));
+        assert!(matches!(
+            decode(&DEFAULT_PASSPHRASE_MNEMONIC.replace("oak", "oaks"), ""),
+            Err(AezeedError::UnknownWord(word)) if word == "oaks"
+        ));
+    }
+
+    #[test]
+    fn derives_lnd_account_addresses() {
+        let aezeed = Aezeed {
+            birthday: 0,
+            entropy: TEST_ENTROPY,
+        };
+
+        let addresses = AddressGenerator::with_script_types(Aezeed::derivation_paths(1))
+            .generate_from_master_key(&aezeed.master_key(), "");
+        let addresses: Vec<(String, String)> = addresses
+            .into_iter()
+            .map(|address| (address.derivation_path.to_string(), address.address))
+            .collect();
+
+        // derived independently of this crate with a plain BIP-32 implementation
+        let expected = [
+            (
+                "m/84'/0'/0'/0/0",
+                "bc1qkhdemh9jxcn2xez07vlgtkkx7gnc09xja4rs5q",
+            ),
+            (
+                "m/84'/0'/0'/1/0",
+                "bc1qjqwq88ednqwkp3n8uhnke67klas47zdu28ezap",
+            ),
+            ("m/49'/0'/0'/0/0", "3Bmhn8iNCk26F1iWR164aj2Qdkii88cXaw"),
+            (
+                "m/49'/0'/0'/1/0",
+                "bc1qmnmnx7g9md2a4k7gh20lxrqkczp2hurywwm063",
+            ),
+        ];
+
+        assert_eq!(
+            addresses,
+            expected.map(|(path, address)| (path.to_string(), address.to_string()))
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use super::check_master_key;
+use super::AddressGenerator;
+use super::Aezeed;
+use super::CheckerError;
+use super::NotificationQueue;
+use super::Secret;
+use super::Target;
+use typed_builder::TypedBuilder;
+
+/// Checks the on-chain wallet of an LND aezeed
+#[derive(TypedBuilder)]
+pub struct AezeedChecker {
+    address_generator: AddressGenerator,
+    notifier: NotificationQueue,
+    target: Target,
+    #[builder(setter(into))]
+    mnemonic: Secret,
+    #[builder(default, setter(into))]
+    passphrase: Secret,
+}
+
+impl AezeedChecker {
+    pub fn check(&self) -> Result<(), CheckerError> {
+        let aezeed = Aezeed::decode(self.mnemonic.expose(), self.passphrase.expose())?;
+        let master_key = aezeed.master_key();
+        let node_key = self
+            .address_generator
+            .account_key(&master_key, &Aezeed::node_key_path());
+
+        log::info!(
+            "Checking aezeed {} with birthday {} days after genesis, node id {}",
+            self.mnemonic,
+            aezeed.birthday(),
+            node_key.public_key
+        );
+
+        check_master_key(
+            &self.target,
+            &self.address_generator,
+            &master_key,
+            self.mnemonic.expose(),
+            &self.notifier,
+        )?;
+
+        Ok(())
+    }
+}
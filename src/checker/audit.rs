@@ -0,0 +1,306 @@
+use super::Slip39Share;
+use bip39::Language;
+use bip39::Mnemonic;
+use bitcoin::hashes::hex::FromHex;
+use std::fmt;
+
+const BITS_PER_WORD: usize = 11;
+const WORD_COUNT: usize = 2048;
+/// Longest repeated entropy pattern reported, the BIP-39 test vectors repeat a single byte
+const MAX_ENTROPY_PATTERN_SIZE: usize = 4;
+/// Entropy with this many leading zero bits is reported as a small number
+const MIN_LEADING_ZEROS: usize = 64;
+/// Larger search spaces can't be enumerated, patterns with them are not reported
+const MAX_SEARCH_SPACE_BITS: f64 = 80.0;
+/// Entropy of the published BIP-39 test vectors (English vectors of python-mnemonic), every
+/// wordlist based search tries them first
+const BIP39_TEST_VECTORS: [&str; 24] = [
+    "00000000000000000000000000000000",
+    "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+    "80808080808080808080808080808080",
+    "ffffffffffffffffffffffffffffffff",
+    "000000000000000000000000000000000000000000000000",
+    "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+    "808080808080808080808080808080808080808080808080",
+    "ffffffffffffffffffffffffffffffffffffffffffffffff",
+    "0000000000000000000000000000000000000000000000000000000000000000",
+    "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+    "8080808080808080808080808080808080808080808080808080808080808080",
+    "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    "9e885d952ad362caeb4efe34a8e91bd2",
+    "6610b25967cdcca9d59875f5cb50b0ea75433311869e930b",
+    "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c",
+    "c0ba5a8e914111210f2bd131f3d5e08d",
+    "6d9be1ee6ebd27a258115aad99b7317b9c8d28b6d76431c3",
+    "9f6a2878b2520799a44ef18bc7df394e7061a224d2c33cd015b157d746869863",
+    "23db8160a31d3e0dca3688ed941adbf3",
+    "8197a4a47f0425faeaa69deebc05ca29c0a5b5cc76ceacc0",
+    "066dca1a2bb7e8a1db2832148ce9933eea0f3ac9548d793112d9a95c9407efad",
+    "f30f8c1da665478f49b001d94c5fc452",
+    "c10ec20dc3cd9f652c7fac2f1230f7a3c828389a14392f05",
+    "f585c11aec520db57dd353c69554b21a89b20fb0650966fa0a9d6f74fd989d8f",
+];
+/// Shares of the published SLIP-39 test vectors (vectors.json of the SLIP-39 reference
+/// implementation)
+const SLIP39_TEST_VECTORS: [&str; 15] = [
+    "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard",
+    "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+    "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+    "enemy favorite academic acid cowboy phrase havoc level response walnut budget painting inside trash adjust froth kitchen learn tidy punish",
+    "eraser senior decision shadow artist work morning estate greatest pipeline plan ting petition forget hormone flexible general goat admit surface",
+    "eraser senior ceramic snake clay various huge numb argue hesitate auction category timber browser greatest hanger petition script leaf pickup",
+    "eraser senior ceramic shaft dynamic become junior wrist silver peasant force math alto coal amazing segment yelp velvet image paces",
+    "eraser senior ceramic round column hawk trust auction smug shame alive greatest sheriff living perfect corner chest sled fumes adequate",
+    "eraser senior decision smug corner ruin rescue cubic angel tackle skin skunk program roster trash rumor slush angel flea amazing",
+    "testify swimming academic academic column loyalty smear include exotic bedroom exotic wrist lobe cover grief golden smart junior estimate learn",
+    "theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck",
+    "humidity disease academic always aluminum jewelry energy woman receiver strategy amuse duckling lying evidence network walnut tactics forget hairy rebound impulse brother survive clothes stadium mailman rival ocean reward venture always armed unwrap",
+    "humidity disease academic agency actress jacket gross physics cylinder solution fake mortgage benefit public busy prepare sharp friar change work slow purchase ruler again tricycle involve viral wireless mixture anatomy desert cargo upgrade",
+    "wildlife deal beard romp alcohol space mild usual clothes union nuclear testify course research heat listen task location thank hospital slice smell failure fawn helpful priest ambition average recover lecture process dough stadium",
+    "wildlife deal acrobat romp anxiety axis starting require metric flexible geology game drove editor edge screw helpful have huge holy making pitch unknown carve holiday numb glasses survive already tenant adapt goat fangs",
+];
+
+/// Search space a phrase falls into
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditFinding {
+    pub description: String,
+    /// log2 of the number of phrases in the search space
+    pub search_space_bits: f64,
+}
+
+/// Checks whether a mnemonic can be found by enumerating a small search space.
+///
+/// The audit only works with word indexes and entropy, findings never contain the words. Phrases
+/// picked by a person, like sentences or song lyrics, are not detected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audit {
+    entropy_bits: usize,
+    findings: Vec<AuditFinding>,
+}
+
+impl Audit {
+    pub fn new(mnemonic: &Mnemonic) -> Audit {
+        let word_list = Language::English.all_words();
+        let indexes: Vec<usize> = mnemonic
+            .word_iter()
+            .map(|word| {
+                word_list
+                    .iter()
+                    .position(|candidate| *candidate == word)
+                    .unwrap()
+            })
+            .collect();
+        let entropy = mnemonic.to_entropy();
+        let entropy_bits = entropy.len() * 8;
+        // the last word is partially taken by the checksum
+        let last_word_bits = (BITS_PER_WORD - entropy_bits / 32) as f64;
+
+        let mut audit = Audit {
+            entropy_bits,
+            findings: vec![],
+        };
+
+        if BIP39_TEST_VECTORS
+            .iter()
+            .any(|vector| Vec::<u8>::from_hex(vector).unwrap() == entropy)
+        {
+            audit.add(
+                "The phrase is a published BIP-39 test vector".to_string(),
+                (BIP39_TEST_VECTORS.len() as f64).log2(),
+            );
+        }
+
+        if let Some(period) = period(&indexes, indexes.len() / 2) {
+            audit.add(
+                format!("The phrase repeats a block of {period} words, see combination-checker"),
+                (period * BITS_PER_WORD) as f64,
+            );
+        }
+
+        let mut distinct = indexes.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+
+        if distinct.len() <= indexes.len() / 2 {
+            let size = distinct.len();
+
+            audit.add(
+                format!("The phrase uses only {size} distinct words"),
+                log2_binomial(WORD_COUNT, size) + indexes.len() as f64 * (size as f64).log2(),
+            );
+        }
+
+        let (leading, _) = indexes.split_at(indexes.len() - 1);
+        let step = (leading[1] + WORD_COUNT - leading[0]) % WORD_COUNT;
+
+        if step != 0
+            && leading
+                .windows(2)
+                .all(|pair| (pair[1] + WORD_COUNT - pair[0]) % WORD_COUNT == step)
+        {
+            audit.add(
+                format!("The words follow the wordlist with a constant step of {step}"),
+                (2 * BITS_PER_WORD) as f64 + last_word_bits,
+            );
+        } else if leading.windows(2).all(|pair| pair[0] < pair[1]) {
+            audit.add(
+                "The words are in alphabetical order".to_string(),
+                log2_binomial(WORD_COUNT, leading.len()) + last_word_bits,
+            );
+        }
+
+        if let Some(size) = period(&entropy, MAX_ENTROPY_PATTERN_SIZE) {
+            audit.add(
+                format!("The entropy repeats a {size} byte pattern, like the BIP-39 test vectors"),
+                (size * 8) as f64,
+            );
+        }
+
+        let leading_zeros = leading_zeros(&entropy);
+
+        if leading_zeros >= MIN_LEADING_ZEROS {
+            audit.add(
+                format!("The entropy is a small number with {leading_zeros} leading zero bits"),
+                (entropy_bits - leading_zeros) as f64,
+            );
+        }
+
+        audit
+    }
+
+    /// SLIP-39 shares are only compared with the published test vectors, the master secret is
+    /// encrypted in the share, so its patterns don't show in the words
+    pub fn slip39(share: &Slip39Share) -> Audit {
+        let mut audit = Audit {
+            entropy_bits: share.secret_bits(),
+            findings: vec![],
+        };
+
+        if SLIP39_TEST_VECTORS.contains(&share.mnemonic()) {
+            audit.add(
+                "The share is a published SLIP-39 test vector".to_string(),
+                (SLIP39_TEST_VECTORS.len() as f64).log2(),
+            );
+        }
+
+        audit
+    }
+
+    pub fn findings(&self) -> &[AuditFinding] {
+        &self.findings
+    }
+
+    pub fn is_weak(&self) -> bool {
+        !self.findings.is_empty()
+    }
+
+    /// Estimated log2 of the smallest search space the phrase falls into
+    pub fn search_space_bits(&self) -> f64 {
+        self.findings
+            .iter()
+            .map(|finding| finding.search_space_bits)
+            .fold(self.entropy_bits as f64, f64::min)
+    }
+
+    fn add(&mut self, description: String, search_space_bits: f64) {
+        if search_space_bits > MAX_SEARCH_SPACE_BITS {
+            return;
+        }
+
+        self.findings.push(AuditFinding {
+            description,
+            search_space_bits,
+        });
+    }
+}
+
+impl fmt::Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(
+                f,
+                "{} (about 2^{:.0} phrases)",
+                finding.description, finding.search_space_bits
+            )?;
+        }
+
+        if self.is_weak() {
+            write!(
+                f,
+                "WEAK: the phrase can be found in about 2^{:.0} guesses",
+                self.search_space_bits()
+            )
+        } else {
+            write!(
+                f,
+                "OK: no known pattern, the search space is 2^{} phrases",
+                self.entropy_bits
+            )
+        }
+    }
+}
+
+/// Smallest period up to `max_period` the values repeat with
+fn period<T: PartialEq>(values: &[T], max_period: usize) -> Option<usize> {
+    (1..=max_period.min(values.len() / 2)).find(|period| {
+        values
+            .iter()
+            .enumerate()
+            .all(|(position, value)| *value == values[position % period])
+    })
+}
+
+fn leading_zeros(bytes: &[u8]) -> usize {
+    let zero_bytes = bytes.iter().take_while(|byte| **byte == 0).count();
+
+    match bytes.get(zero_bytes) {
+        Some(byte) => zero_bytes * 8 + byte.leading_zeros() as usize,
+        None => zero_bytes * 8,
+    }
+}
+
+fn log2_binomial(n: usize, k: usize) -> f64 {
+    (0..k)
+        .map(|i| ((n - i) as f64).log2() - ((i + 1) as f64).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::Slip39Wordlist;
+
+    fn audit_entropy(entropy: &str) -> Audit {
+        let mnemonic = Mnemonic::from_entropy(&Vec::<u8>::from_hex(entropy).unwrap()).unwrap();
+
+        Audit::new(&mnemonic)
+    }
+
+    #[test]
+    fn finds_bip39_test_vectors() {
+        for vector in BIP39_TEST_VECTORS {
+            assert!(audit_entropy(vector).is_weak(), "{vector}");
+        }
+
+        let audit = audit_entropy("9e885d952ad362caeb4efe34a8e91bd2");
+        assert_eq!(audit.findings().len(), 1);
+        assert_eq!(
+            audit.findings()[0].description,
+            "The phrase is a published BIP-39 test vector"
+        );
+    }
+
+    #[test]
+    fn random_entropy_is_not_weak() {
+        assert!(!audit_entropy("9e885d952ad362caeb4efe34a8e91bd3").is_weak());
+    }
+
+    #[test]
+    fn finds_slip39_test_vectors() {
+        for vector in SLIP39_TEST_VECTORS {
+            let share = Slip39Share::parse(vector, Slip39Wordlist::english()).unwrap();
+
+            assert!(Audit::slip39(&share).is_weak(), "{vector}");
+        }
+    }
+}
@@ -1,8 +1,13 @@
-use super::check_address;
+use super::check_candidate;
+use super::check_shard_count;
+use super::report_progress;
 use super::AddressGenerator;
 use super::CheckerError;
-use super::TelegramClient;
-use crate::Repo;
+use super::NotificationQueue;
+use super::Secret;
+use super::Shard;
+use super::Target;
+use super::PROGRESS_INTERVAL;
 use bip39::Language;
 use bip39::Mnemonic;
 use itertools::Itertools;
@@ -13,23 +18,39 @@ pub struct CombinationChecker {
     combination: usize,
     mnemonic_size: usize,
     address_generator: AddressGenerator,
-    telegram_client: Option<TelegramClient>,
-    repo: Repo,
+    notifier: NotificationQueue,
+    target: Target,
+    #[builder(default)]
+    shard: Shard,
+    #[builder(setter(into))]
+    search: String,
 }
 
 impl CombinationChecker {
     pub fn check(&self) -> Result<(), CheckerError> {
-        for mnemonic in self.mnemonics() {
-            log::info!("Checking mnemonic {mnemonic}");
+        check_shard_count(&self.target, &self.search, &self.shard)?;
 
-            let addresses = self.address_generator.generate(mnemonic);
+        let mnemonics = self.mnemonics();
+        let total = mnemonics.len();
 
-            for address in addresses {
-                check_address(&self.repo, &address, &self.telegram_client)?;
+        for (position, mnemonic) in mnemonics.into_iter().enumerate() {
+            log::info!("Checking mnemonic {}", Secret::from(&mnemonic));
+
+            check_candidate(
+                &self.target,
+                &self.address_generator,
+                mnemonic,
+                &self.notifier,
+            )?;
+
+            let checked = position + 1;
+
+            if checked % PROGRESS_INTERVAL == 0 {
+                report_progress(&self.target, &self.search, &self.shard, checked, total)?;
             }
         }
 
-        Ok(())
+        report_progress(&self.target, &self.search, &self.shard, total, total)
     }
 
     fn mnemonics(&self) -> Vec<Mnemonic> {
@@ -40,7 +61,9 @@ impl CombinationChecker {
             .map(String::from)
             .into_iter()
             .combinations(self.combination)
-            .map(|combination| {
+            .enumerate()
+            .filter(|(position, _)| self.shard.contains(*position))
+            .map(|(_, combination)| {
                 let mut result = vec![];
                 for _i in 0..dup_times {
                     result.extend(combination.clone());
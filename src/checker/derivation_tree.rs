@@ -0,0 +1,177 @@
+use bitcoin::secp256k1::All;
+use bitcoin::util::bip32::ChildNumber;
+use bitcoin::util::bip32::DerivationPath;
+use bitcoin::util::bip32::ExtendedPrivKey;
+use bitcoin::util::bip32::ExtendedPubKey;
+use bitcoin::util::key::Secp256k1;
+
+/// Prefix tree of derivation paths.
+///
+/// Every intermediate extended key is derived once per master key no matter how many
+/// configured paths share it. Private derivation is only used while hardened levels remain
+/// below a node, the rest of the subtree is derived from the extended public key.
+#[derive(Default, Debug)]
+pub struct DerivationTree {
+    targets: Vec<usize>,
+    children: Vec<(ChildNumber, DerivationTree)>,
+}
+
+impl DerivationTree {
+    pub fn new(derivation_paths: &[DerivationPath]) -> DerivationTree {
+        let mut tree = DerivationTree::default();
+
+        for (target, path) in derivation_paths.iter().enumerate() {
+            tree.insert(path.as_ref(), target);
+        }
+
+        tree
+    }
+
+    /// Returns public keys in the order of the paths the tree was built from
+    pub fn derive(
+        &self,
+        secp256k1: &Secp256k1<All>,
+        master_key: &ExtendedPrivKey,
+    ) -> Vec<ExtendedPubKey> {
+        let mut public_keys = vec![None; self.len()];
+
+        self.derive_node(secp256k1, Some(master_key), None, &mut public_keys);
+
+        public_keys
+            .into_iter()
+            .map(|public_key| public_key.unwrap())
+            .collect()
+    }
+
+    fn insert(&mut self, path: &[ChildNumber], target: usize) {
+        match path.split_first() {
+            None => self.targets.push(target),
+            Some((child_number, rest)) => {
+                let position = match self
+                    .children
+                    .iter()
+                    .position(|(number, _)| number == child_number)
+                {
+                    Some(position) => position,
+                    None => {
+                        self.children
+                            .push((*child_number, DerivationTree::default()));
+                        self.children.len() - 1
+                    }
+                };
+
+                self.children[position].1.insert(rest, target);
+            }
+        }
+    }
+
+    fn derive_node(
+        &self,
+        secp256k1: &Secp256k1<All>,
+        private_key: Option<&ExtendedPrivKey>,
+        public_key: Option<ExtendedPubKey>,
+        public_keys: &mut [Option<ExtendedPubKey>],
+    ) {
+        let needs_public_key = !self.targets.is_empty()
+            || self
+                .children
+                .iter()
+                .any(|(child_number, subtree)| !is_private_branch(child_number, subtree));
+
+        let public_key = match (public_key, private_key) {
+            (Some(public_key), _) => Some(public_key),
+            (None, Some(private_key)) if needs_public_key => {
+                Some(ExtendedPubKey::from_priv(secp256k1, private_key))
+            }
+            _ => None,
+        };
+
+        for target in &self.targets {
+            public_keys[*target] = public_key;
+        }
+
+        for (child_number, subtree) in &self.children {
+            if is_private_branch(child_number, subtree) {
+                let child_key = private_key
+                    .unwrap()
+                    .ckd_priv(secp256k1, *child_number)
+                    .unwrap();
+
+                subtree.derive_node(secp256k1, Some(&child_key), None, public_keys);
+            } else {
+                let child_key = public_key
+                    .unwrap()
+                    .ckd_pub(secp256k1, *child_number)
+                    .unwrap();
+
+                subtree.derive_node(secp256k1, None, Some(child_key), public_keys);
+            }
+        }
+    }
+
+    fn requires_private_key(&self) -> bool {
+        self.children
+            .iter()
+            .any(|(child_number, subtree)| is_private_branch(child_number, subtree))
+    }
+
+    fn len(&self) -> usize {
+        self.targets.len()
+            + self
+                .children
+                .iter()
+                .map(|(_, subtree)| subtree.len())
+                .sum::<usize>()
+    }
+}
+
+fn is_private_branch(child_number: &ChildNumber, subtree: &DerivationTree) -> bool {
+    child_number.is_hardened() || subtree.requires_private_key()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::network::constants::Network;
+    use std::str::FromStr;
+
+    #[test]
+    fn derives_like_direct_private_derivation() {
+        let secp256k1 = Secp256k1::new();
+        let master_key = ExtendedPrivKey::new_master(Network::Bitcoin, &[7; 32]).unwrap();
+
+        // shared hardened and unhardened prefixes, a hardened level below an unhardened one,
+        // duplicates, a prefix of another path and the master key itself
+        let derivation_paths: Vec<DerivationPath> = [
+            "m/84'/0'/0'/0/0",
+            "m/84'/0'/0'/0/1",
+            "m/84'/0'/0'/1/0",
+            "m/84'/0'/1'/0/0",
+            "m/84'/0'/0'",
+            "m/84'/0'/0'/0/0",
+            "m/0/1/2",
+            "m/0/1",
+            "m/0/1'/2",
+            "m/0/1'/2/3",
+            "m/5/6'",
+            "m",
+        ]
+        .iter()
+        .map(|path| DerivationPath::from_str(path).unwrap())
+        .collect();
+
+        let public_keys = DerivationTree::new(&derivation_paths).derive(&secp256k1, &master_key);
+
+        assert_eq!(public_keys.len(), derivation_paths.len());
+
+        for (path, public_key) in derivation_paths.iter().zip(public_keys) {
+            let private_key = master_key.derive_priv(&secp256k1, path).unwrap();
+
+            assert_eq!(
+                public_key,
+                ExtendedPubKey::from_priv(&secp256k1, &private_key),
+                "{path}"
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use super::ScriptType;
+use bitcoin::util::bip32;
+use bitcoin::util::bip32::ChildNumber;
+use bitcoin::util::bip32::DerivationPath;
+use std::ops::Range;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Placeholders for the candidate master key in descriptors
+const KEY_PLACEHOLDERS: [&str; 2] = ["KEY", "m"];
+
+#[derive(Error, Debug)]
+pub enum DescriptorError {
+    #[error("Unsupported script {0}, supported scripts are pkh, wpkh, sh(wpkh) and tr")]
+    UnsupportedScript(String),
+    #[error("Invalid key {0}, the key must be one of {KEY_PLACEHOLDERS:?}")]
+    InvalidKey(String),
+    #[error("Wildcard must be the last path element")]
+    MisplacedWildcard,
+    #[error("Derivation path error {0:?}")]
+    DerivationPathError(#[from] bip32::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Wildcard {
+    Normal,
+    Hardened,
+}
+
+/// Output descriptor for candidate master keys.
+///
+/// Supports `pkh(KEY/..)`, `wpkh(KEY/..)`, `sh(wpkh(KEY/..))` and `tr(KEY/..)` where `KEY` (or `m`)
+/// is the candidate master key. The path may end with a `*` or `*'` wildcard, which is expanded
+/// over a range of indexes. A trailing checksum (`#...`) is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Descriptor {
+    script_type: ScriptType,
+    derivation_path: DerivationPath,
+    wildcard: Option<Wildcard>,
+}
+
+impl Descriptor {
+    pub fn script_type(&self) -> ScriptType {
+        self.script_type
+    }
+
+    /// Derivation paths the descriptor covers, `range` is used only for ranged descriptors
+    pub fn derivation_paths(&self, range: Range<u32>) -> Result<Vec<DerivationPath>, bip32::Error> {
+        match self.wildcard {
+            None => Ok(vec![self.derivation_path.clone()]),
+            Some(wildcard) => range
+                .map(|index| {
+                    let child_number = match wildcard {
+                        Wildcard::Normal => ChildNumber::from_normal_idx(index)?,
+                        Wildcard::Hardened => ChildNumber::from_hardened_idx(index)?,
+                    };
+
+                    Ok(self.derivation_path.child(child_number))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Descriptor {
+    type Err = DescriptorError;
+
+    fn from_str(raw_descriptor: &str) -> Result<Self, Self::Err> {
+        let descriptor = raw_descriptor
+            .split_once('#')
+            .map_or(raw_descriptor, |(descriptor, _checksum)| descriptor)
+            .trim();
+
+        let (script_type, key_expression) = [
+            ("sh(wpkh(", "))", ScriptType::P2shP2wpkh),
+            ("wpkh(", ")", ScriptType::P2wpkh),
+            ("pkh(", ")", ScriptType::P2pkh),
+            ("tr(", ")", ScriptType::P2tr),
+        ]
+        .into_iter()
+        .find_map(|(prefix, suffix, script_type)| {
+            descriptor
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .map(|key_expression| (script_type, key_expression))
+        })
+        .ok_or_else(|| DescriptorError::UnsupportedScript(descriptor.to_string()))?;
+
+        let mut elements = key_expression.split('/');
+        let key = elements.next().unwrap_or_default();
+
+        if !KEY_PLACEHOLDERS.contains(&key) {
+            return Err(DescriptorError::InvalidKey(key.to_string()));
+        }
+
+        let mut child_numbers = vec![];
+        let mut wildcard = None;
+
+        for element in elements {
+            if wildcard.is_some() {
+                return Err(DescriptorError::MisplacedWildcard);
+            }
+
+            match element {
+                "*" => wildcard = Some(Wildcard::Normal),
+                "*'" | "*h" => wildcard = Some(Wildcard::Hardened),
+                element => child_numbers.push(ChildNumber::from_str(element)?),
+            }
+        }
+
+        Ok(Descriptor {
+            script_type,
+            derivation_path: DerivationPath::from(child_numbers),
+            wildcard,
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Placeholder for unknown hex digits and bits
+const UNKNOWN: char = '?';
+/// BIP-39 entropy sizes in bits
+const ENTROPY_SIZES: [usize; 5] = [128, 160, 192, 224, 256];
+/// Upper bound of the search space, 2^24 candidates
+const MAX_UNKNOWN_BITS: usize = 24;
+
+#[derive(Error, Debug)]
+pub enum EntropyError {
+    #[error("Invalid entropy character {0}, expected a hex digit, a bit or {UNKNOWN}")]
+    InvalidCharacter(char),
+    #[error("Invalid entropy size {0} bits, BIP-39 entropy has {ENTROPY_SIZES:?} bits")]
+    InvalidSize(usize),
+    #[error("{0} unknown bits, at most {MAX_UNKNOWN_BITS} can be recovered")]
+    TooManyUnknownBits(usize),
+}
+
+/// BIP-39 entropy with possibly unknown bits.
+///
+/// Parsed from hex (`?` is an unknown nibble) or, for dice and coin flip backups, from binary
+/// (`?` is an unknown bit). Binary entropy is at least 128 characters long, hex entropy at most
+/// 64, so the notation is taken from the length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntropyPattern {
+    bits: Vec<Option<bool>>,
+}
+
+impl EntropyPattern {
+    pub fn unknown_bits(&self) -> usize {
+        self.bits.iter().filter(|bit| bit.is_none()).count()
+    }
+
+    pub fn candidate_count(&self) -> u64 {
+        1 << self.unknown_bits()
+    }
+
+    /// All entropies matching the pattern
+    pub fn candidates(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (0..self.candidate_count()).map(|candidate| self.candidate(candidate))
+    }
+
+    /// Entropy with the bits of `candidate` in place of the unknown bits, the last unknown bit is
+    /// the lowest bit of `candidate`
+    pub fn candidate(&self, candidate: u64) -> Vec<u8> {
+        let mut unknown_position = self.unknown_bits();
+
+        let bits: Vec<bool> = self
+            .bits
+            .iter()
+            .map(|bit| {
+                bit.unwrap_or_else(|| {
+                    unknown_position -= 1;
+                    (candidate >> unknown_position) & 1 == 1
+                })
+            })
+            .collect();
+
+        bits.chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+            .collect()
+    }
+}
+
+impl FromStr for EntropyPattern {
+    type Err = EntropyError;
+
+    fn from_str(raw_entropy: &str) -> Result<Self, Self::Err> {
+        let raw_entropy = raw_entropy.trim();
+        let raw_entropy = raw_entropy.strip_prefix("0x").unwrap_or(raw_entropy);
+        let binary = raw_entropy.len() >= ENTROPY_SIZES[0];
+
+        let mut bits = vec![];
+
+        for character in raw_entropy.chars() {
+            if character == UNKNOWN {
+                let size = if binary { 1 } else { 4 };
+                bits.extend(std::iter::repeat_n(None, size));
+                continue;
+            }
+
+            let radix = if binary { 2 } else { 16 };
+            let value = character
+                .to_digit(radix)
+                .ok_or(EntropyError::InvalidCharacter(character))?;
+
+            if binary {
+                bits.push(Some(value == 1));
+            } else {
+                bits.extend((0..4).rev().map(|bit| Some((value >> bit) & 1 == 1)));
+            }
+        }
+
+        if !ENTROPY_SIZES.contains(&bits.len()) {
+            return Err(EntropyError::InvalidSize(bits.len()));
+        }
+
+        let pattern = EntropyPattern { bits };
+
+        if pattern.unknown_bits() > MAX_UNKNOWN_BITS {
+            return Err(EntropyError::TooManyUnknownBits(pattern.unknown_bits()));
+        }
+
+        Ok(pattern)
+    }
+}
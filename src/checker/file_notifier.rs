@@ -0,0 +1,32 @@
+use super::Notification;
+use super::Notifier;
+use super::NotifierError;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Appends notifications to a local file, one JSON object per line
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        let line = serde_json::to_string(notification).unwrap();
+
+        writeln!(file, "{line}")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use super::CheckerError;
+use crate::FoundAddress;
+use crate::SecretEncryptor;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+/// Where found addresses go when there is no database to store them in.
+///
+/// Found addresses are appended to a file, or printed to stdout without one, as one JSON object per
+/// line. Unlike in logs the mnemonic is written as is, or age encrypted if a recipient is given.
+/// A new file is created readable by its owner only.
+pub struct FoundOutput {
+    path: Option<PathBuf>,
+    encryptor: Option<SecretEncryptor>,
+}
+
+impl FoundOutput {
+    pub fn new(path: Option<PathBuf>, encryptor: Option<SecretEncryptor>) -> Self {
+        Self { path, encryptor }
+    }
+
+    pub fn write(&self, mut found_address: FoundAddress) -> Result<(), CheckerError> {
+        if let Some(encryptor) = &self.encryptor {
+            let ciphertext = encryptor.encrypt(&found_address.mnemonic)?;
+            found_address.mnemonic.zeroize();
+            found_address.mnemonic = ciphertext;
+        }
+
+        let line = Zeroizing::new(serde_json::to_string(&found_address).unwrap());
+
+        match &self.path {
+            Some(path) => {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .mode(0o600)
+                    .open(path)?;
+
+                writeln!(file, "{}", *line)?;
+            }
+            None => writeln!(io::stdout().lock(), "{}", *line)?,
+        }
+
+        Ok(())
+    }
+}
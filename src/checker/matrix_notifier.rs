@@ -0,0 +1,57 @@
+use super::notifier::notifier_agent;
+use super::Notification;
+use super::Notifier;
+use super::NotifierError;
+use typed_builder::TypedBuilder;
+
+/// Sends notifications as text messages to a Matrix room
+#[derive(TypedBuilder)]
+pub struct MatrixNotifier {
+    #[builder(default_code = "notifier_agent()")]
+    request_agent: ureq::Agent,
+    /// Homeserver URL, e.g. `https://matrix.org`
+    #[builder(setter(into))]
+    homeserver: String,
+    #[builder(setter(into))]
+    access_token: String,
+    /// Room id like `!room:matrix.org`
+    #[builder(setter(into))]
+    room_id: String,
+}
+
+impl Notifier for MatrixNotifier {
+    /// The notification id is the transaction id, the homeserver ignores retries of a sent message
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+        let url = format!(
+            "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+            self.homeserver.trim_end_matches('/'),
+            encode_path_segment(&self.room_id),
+            encode_path_segment(&notification.id)
+        );
+
+        let body = serde_json::json!({
+            "msgtype": "m.text",
+            "body": notification.message,
+        });
+
+        self.request_agent
+            .put(&url)
+            .set("Authorization", &format!("Bearer {}", self.access_token))
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())?;
+
+        Ok(())
+    }
+}
+
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -1,29 +1,125 @@
-use super::check_address;
+use super::check_candidate;
+use super::check_master_key;
+use super::check_shard_count;
+use super::report_progress;
 use super::AddressGenerator;
 use super::CheckerError;
-use super::TelegramClient;
-use crate::Repo;
+use super::EntropyPattern;
+use super::NotificationQueue;
+use super::Redacted;
+use super::Secret;
+use super::Shard;
+use super::Target;
+use super::PROGRESS_INTERVAL;
 use bip39::Mnemonic;
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::util::bip32::ExtendedPrivKey;
 use typed_builder::TypedBuilder;
+use zeroize::Zeroizing;
+
+/// Backup forms of a single wallet
+pub enum MnemonicInput {
+    Mnemonic(Mnemonic),
+    /// BIP-39 entropy, unknown bits are recovered by checking every candidate
+    Entropy(EntropyPattern),
+    /// BIP-32 seed, e.g. the BIP-39 seed of a mnemonic with a passphrase
+    Seed(Zeroizing<Vec<u8>>),
+    ExtendedPrivKey(ExtendedPrivKey),
+}
 
 #[derive(TypedBuilder)]
 pub struct MnemonicChecker {
     address_generator: AddressGenerator,
-    telegram_client: Option<TelegramClient>,
-    repo: Repo,
-    mnemonic: Mnemonic,
+    notifier: NotificationQueue,
+    target: Target,
+    #[builder(setter(into))]
+    input: MnemonicInput,
+    /// Slice of the entropy candidates to check, other inputs have a single candidate
+    #[builder(default)]
+    shard: Shard,
+    /// Name under which the progress of entropy recovery is stored
+    #[builder(setter(into))]
+    search: String,
 }
 
 impl MnemonicChecker {
     pub fn check(&self) -> Result<(), CheckerError> {
-        log::info!("Checking mnemonic {}", self.mnemonic);
+        match &self.input {
+            MnemonicInput::Mnemonic(mnemonic) => self.check_mnemonic(mnemonic.clone()),
+            MnemonicInput::Entropy(pattern) => self.check_entropy(pattern),
+            MnemonicInput::Seed(seed) => {
+                let master_key = AddressGenerator::master_key_from_seed(seed);
+
+                self.check_master_key(&master_key, Secret::new(seed.to_hex()).expose())
+            }
+            MnemonicInput::ExtendedPrivKey(master_key) => {
+                self.check_master_key(master_key, Secret::new(master_key.to_string()).expose())
+            }
+        }
+    }
+
+    fn check_entropy(&self, pattern: &EntropyPattern) -> Result<(), CheckerError> {
+        check_shard_count(&self.target, &self.search, &self.shard)?;
 
-        let addresses = self.address_generator.generate(self.mnemonic.clone());
+        let total = self.shard.size(pattern.candidate_count() as usize);
 
-        for address in addresses {
-            check_address(&self.repo, &address, &self.telegram_client)?;
+        if pattern.unknown_bits() > 0 {
+            log::info!(
+                "Recovering {} unknown entropy bits, {total} of {} candidates in the shard",
+                pattern.unknown_bits(),
+                pattern.candidate_count()
+            );
         }
 
-        Ok(())
+        let entropies = (0..pattern.candidate_count())
+            .filter(|candidate| self.shard.contains(*candidate as usize))
+            .map(|candidate| Zeroizing::new(pattern.candidate(candidate)));
+
+        for (position, entropy) in entropies.enumerate() {
+            let mnemonic = Mnemonic::from_entropy(&entropy).unwrap();
+
+            self.check_mnemonic(mnemonic)?;
+
+            let checked = position + 1;
+
+            if checked % PROGRESS_INTERVAL == 0 {
+                report_progress(&self.target, &self.search, &self.shard, checked, total)?;
+            }
+        }
+
+        report_progress(&self.target, &self.search, &self.shard, total, total)
+    }
+
+    fn check_mnemonic(&self, mnemonic: Mnemonic) -> Result<(), CheckerError> {
+        log::info!("Checking mnemonic {}", Secret::from(&mnemonic));
+
+        check_candidate(
+            &self.target,
+            &self.address_generator,
+            mnemonic,
+            &self.notifier,
+        )
+    }
+
+    fn check_master_key(
+        &self,
+        master_key: &ExtendedPrivKey,
+        secret: &str,
+    ) -> Result<(), CheckerError> {
+        log::info!("Checking master key of {}", Redacted(secret));
+
+        check_master_key(
+            &self.target,
+            &self.address_generator,
+            master_key,
+            secret,
+            &self.notifier,
+        )
+    }
+}
+
+impl From<Mnemonic> for MnemonicInput {
+    fn from(mnemonic: Mnemonic) -> Self {
+        MnemonicInput::Mnemonic(mnemonic)
     }
 }
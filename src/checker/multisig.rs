@@ -0,0 +1,274 @@
+use bitcoin::blockdata::opcodes::all::OP_CHECKMULTISIG;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::network::constants::Network;
+use bitcoin::secp256k1;
+use bitcoin::util::address::Address as BitcoinAddress;
+use bitcoin::util::bip32::ChildNumber;
+use bitcoin::util::bip32::DerivationPath;
+use bitcoin::util::bip32::Error;
+use bitcoin::util::bip32::ExtendedPubKey;
+use bitcoin::util::key::PublicKey;
+use bitcoin::util::key::Secp256k1;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+const CHAINS: [u32; 2] = [0, 1];
+const MAX_P2SH_KEYS: usize = 15;
+const MAX_WITNESS_KEYS: usize = 20;
+
+#[derive(Error, Debug)]
+pub enum MultisigError {
+    #[error("Multisig threshold must be between 1 and {key_count}, got {threshold}")]
+    InvalidThreshold { threshold: usize, key_count: usize },
+    #[error("{script_type} multisig supports up to {max_keys} keys, got {key_count}")]
+    TooManyKeys {
+        script_type: MultisigScriptType,
+        max_keys: usize,
+        key_count: usize,
+    },
+    #[error("Extended Key Error {0:?}")]
+    KeyError(#[from] Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultisigScriptType {
+    P2sh,
+    P2shP2wsh,
+    P2wsh,
+}
+
+impl MultisigScriptType {
+    /// BIP-48 account path of the first account, `P2sh` has no standard path
+    pub fn default_account_path(&self) -> Option<DerivationPath> {
+        let script_index = match self {
+            MultisigScriptType::P2sh => return None,
+            MultisigScriptType::P2shP2wsh => 1,
+            MultisigScriptType::P2wsh => 2,
+        };
+
+        Some(DerivationPath::from(vec![
+            ChildNumber::Hardened { index: 48 },
+            ChildNumber::Hardened { index: 0 },
+            ChildNumber::Hardened { index: 0 },
+            ChildNumber::Hardened {
+                index: script_index,
+            },
+        ]))
+    }
+
+    fn max_keys(&self) -> usize {
+        match self {
+            MultisigScriptType::P2sh => MAX_P2SH_KEYS,
+            MultisigScriptType::P2shP2wsh | MultisigScriptType::P2wsh => MAX_WITNESS_KEYS,
+        }
+    }
+}
+
+impl FromStr for MultisigScriptType {
+    type Err = String;
+
+    fn from_str(raw_script_type: &str) -> Result<Self, Self::Err> {
+        match raw_script_type {
+            "p2sh" => Ok(MultisigScriptType::P2sh),
+            "p2sh-p2wsh" => Ok(MultisigScriptType::P2shP2wsh),
+            "p2wsh" => Ok(MultisigScriptType::P2wsh),
+            _ => Err(format!(
+                "unknown multisig script type {raw_script_type}, supported types are p2sh, p2sh-p2wsh and p2wsh"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MultisigScriptType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MultisigScriptType::P2sh => "p2sh",
+            MultisigScriptType::P2shP2wsh => "p2sh-p2wsh",
+            MultisigScriptType::P2wsh => "p2wsh",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Sorted multisig (BIP-67) where one key comes from the checked mnemonic.
+///
+/// The candidate key is derived at `account_path/chain/index`, cosigner keys at `chain/index`
+/// relative to their account keys, for both receive and change chains.
+#[derive(Debug)]
+pub struct Multisig {
+    script_type: MultisigScriptType,
+    threshold: usize,
+    account_path: DerivationPath,
+    derivation_paths: Vec<DerivationPath>,
+    cosigner_keys: Vec<Vec<PublicKey>>,
+}
+
+impl Multisig {
+    pub fn new(
+        script_type: MultisigScriptType,
+        threshold: usize,
+        cosigners: Vec<ExtendedPubKey>,
+        account_path: DerivationPath,
+        address_count: u32,
+    ) -> Result<Self, MultisigError> {
+        let key_count = cosigners.len() + 1;
+
+        if threshold == 0 || threshold > key_count {
+            return Err(MultisigError::InvalidThreshold {
+                threshold,
+                key_count,
+            });
+        }
+
+        if key_count > script_type.max_keys() {
+            return Err(MultisigError::TooManyKeys {
+                script_type,
+                max_keys: script_type.max_keys(),
+                key_count,
+            });
+        }
+
+        let secp256k1 = Secp256k1::verification_only();
+        let mut derivation_paths = vec![];
+        let mut cosigner_keys = vec![];
+
+        for chain in CHAINS {
+            for index in 0..address_count {
+                let relative_path = DerivationPath::from(vec![
+                    ChildNumber::Normal { index: chain },
+                    ChildNumber::Normal { index },
+                ]);
+
+                let keys = cosigners
+                    .iter()
+                    .map(|cosigner| {
+                        let key = cosigner.derive_pub(&secp256k1, &relative_path)?;
+
+                        Ok(PublicKey::new(key.public_key))
+                    })
+                    .collect::<Result<_, Error>>()?;
+
+                derivation_paths.push(account_path.extend(&relative_path));
+                cosigner_keys.push(keys);
+            }
+        }
+
+        Ok(Self {
+            script_type,
+            threshold,
+            account_path,
+            derivation_paths,
+            cosigner_keys,
+        })
+    }
+
+    pub fn script_type(&self) -> MultisigScriptType {
+        self.script_type
+    }
+
+    pub fn account_path(&self) -> &DerivationPath {
+        &self.account_path
+    }
+
+    /// Paths of the candidate key, one per address
+    pub fn derivation_paths(&self) -> &[DerivationPath] {
+        &self.derivation_paths
+    }
+
+    /// Address at `position` of `derivation_paths` with the candidate key `public_key`
+    pub fn address(&self, position: usize, public_key: secp256k1::PublicKey) -> BitcoinAddress {
+        let mut keys = self.cosigner_keys[position].clone();
+        keys.push(PublicKey::new(public_key));
+        keys.sort_by_key(|key| key.to_bytes());
+
+        let mut builder = Builder::new().push_int(self.threshold as i64);
+
+        for key in &keys {
+            builder = builder.push_key(key);
+        }
+
+        let script = builder
+            .push_int(keys.len() as i64)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script();
+
+        match self.script_type {
+            MultisigScriptType::P2sh => BitcoinAddress::p2sh(&script, Network::Bitcoin).unwrap(),
+            MultisigScriptType::P2shP2wsh => BitcoinAddress::p2shwsh(&script, Network::Bitcoin),
+            MultisigScriptType::P2wsh => BitcoinAddress::p2wsh(&script, Network::Bitcoin),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2-of-2 vector of BIP-67, the keys are sorted in the script
+    const FIRST_KEY: &str = "02ff12471208c14bd580709cb2358d98975247d8765f92bc25eab3b2763ed605f8";
+    const SECOND_KEY: &str = "02fe6f0a5a297eb38c391581c4413e084773ea23954d93f7753db7dc0adc188b2f";
+
+    fn two_of_two(script_type: MultisigScriptType) -> Multisig {
+        Multisig {
+            script_type,
+            threshold: 2,
+            account_path: DerivationPath::master(),
+            derivation_paths: vec![DerivationPath::master()],
+            cosigner_keys: vec![vec![PublicKey::from_str(FIRST_KEY).unwrap()]],
+        }
+    }
+
+    #[test]
+    fn sorts_keys_of_the_script() {
+        let candidate = secp256k1::PublicKey::from_str(SECOND_KEY).unwrap();
+
+        for (script_type, address) in [
+            // the address of the BIP-67 vector
+            (
+                MultisigScriptType::P2sh,
+                "39bgKC7RFbpoCRbtD5KEdkYKtNyhpsNa3Z",
+            ),
+            (
+                MultisigScriptType::P2shP2wsh,
+                "3BBLivaThSP3C31jzmQJiMWBM7BLndaWfh",
+            ),
+            (
+                MultisigScriptType::P2wsh,
+                "bc1qknwt9mhqpd7hrjrvpqz57zjqk28xlp2h90te6v22en0m3uctnams3pq5ce",
+            ),
+        ] {
+            assert_eq!(
+                two_of_two(script_type).address(0, candidate).to_string(),
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_thresholds() {
+        let new = |threshold| {
+            Multisig::new(
+                MultisigScriptType::P2wsh,
+                threshold,
+                vec![],
+                DerivationPath::master(),
+                1,
+            )
+        };
+
+        assert!(matches!(
+            new(0),
+            Err(MultisigError::InvalidThreshold { threshold: 0, .. })
+        ));
+        assert!(matches!(
+            new(2),
+            Err(MultisigError::InvalidThreshold {
+                threshold: 2,
+                key_count: 1
+            })
+        ));
+        assert!(new(1).is_ok());
+    }
+}
@@ -0,0 +1,748 @@
+use super::Notification;
+use super::Notifier;
+use super::NotifierError;
+use super::Notifiers;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::fs::TryLockError;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+/// Deliveries are given up after this many attempts, the notification stays in the spool file
+const MAX_ATTEMPTS: u32 = 10;
+/// How long the worker waits for new notifications when nothing is scheduled
+const IDLE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Delivers notifications on a background thread so the search never waits for a notifier.
+///
+/// Failed sinks are retried with exponential backoff. Notifications that are not delivered yet
+/// are kept in a spool file of the process next to the configured path. It's locked while the
+/// process runs, on start the spools that aren't locked any more are taken over and retried.
+pub struct NotificationQueue {
+    sender: Option<Sender<Notification>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct PendingNotification {
+    notification: Notification,
+    /// Indexes of the sinks that haven't received the notification yet
+    sinks: Vec<usize>,
+    attempts: u32,
+    next_attempt_at: Instant,
+}
+
+struct Worker {
+    notifiers: Notifiers,
+    spool: Option<Spool>,
+    pending: Vec<PendingNotification>,
+    /// Notifications that ran out of attempts, kept in the spool file only
+    undelivered: Vec<Notification>,
+}
+
+/// Spool file written by one process only
+struct Spool {
+    /// Configured path, spool files of processes are named after it
+    base_path: PathBuf,
+    path: PathBuf,
+    /// Held while the process runs, so other processes don't take over the spool
+    lock: File,
+}
+
+/// Spool file of another process that isn't running any more
+struct AdoptedSpool {
+    path: PathBuf,
+    /// Lock of the adopted file, none for the spool of this process
+    lock: Option<File>,
+    notifications: Vec<Notification>,
+}
+
+impl NotificationQueue {
+    pub fn start(notifiers: Notifiers, spool_file: Option<PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        // without notifiers nothing is spooled, the spools are left for a run with notifiers
+        let spool_file = spool_file.filter(|_| !notifiers.is_empty());
+        let spool = spool_file.and_then(|path| match Spool::open(&path, process::id()) {
+            Ok(spool) => Some(spool),
+            Err(error) => {
+                log::error!("Failed to lock notification spool of {path:?} - {error:?}");
+                None
+            }
+        });
+        let mut worker = Worker::new(notifiers, spool);
+
+        let worker = thread::Builder::new()
+            .name("notifications".to_string())
+            .spawn(move || worker.run(receiver))
+            .expect("failed to start the notification thread");
+
+        Self {
+            sender: Some(sender),
+            worker: Some(worker),
+        }
+    }
+}
+
+impl Notifier for NotificationQueue {
+    /// Queues the notification, it only fails if the worker thread died
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+        self.sender
+            .as_ref()
+            .ok_or(NotifierError::QueueClosed)?
+            .send(notification.clone())
+            .map_err(|_| NotifierError::QueueClosed)
+    }
+}
+
+impl Drop for NotificationQueue {
+    /// Makes a last delivery attempt for the queued notifications and waits for it to finish
+    fn drop(&mut self) {
+        self.sender.take();
+
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                log::error!("Notification thread panicked");
+            }
+        }
+    }
+}
+
+impl Worker {
+    fn new(notifiers: Notifiers, spool: Option<Spool>) -> Self {
+        Self {
+            notifiers,
+            spool,
+            pending: vec![],
+            undelivered: vec![],
+        }
+    }
+
+    fn run(&mut self, receiver: Receiver<Notification>) {
+        if self.notifiers.is_empty() {
+            // nothing to deliver to, the spool file is left for a run with notifiers
+            while receiver.recv().is_ok() {}
+
+            return;
+        }
+
+        self.adopt_spools();
+        self.deliver(false);
+
+        loop {
+            let timeout = self
+                .pending
+                .iter()
+                .map(|pending| pending.next_attempt_at)
+                .min()
+                .map(|next_attempt_at| next_attempt_at.saturating_duration_since(Instant::now()))
+                .unwrap_or(IDLE_TIMEOUT);
+
+            match receiver.recv_timeout(timeout) {
+                Ok(notification) => self.push(notification),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            self.deliver(false);
+        }
+
+        self.deliver(true);
+
+        let undelivered = self.pending.len() + self.undelivered.len();
+
+        if undelivered > 0 {
+            log::error!("{undelivered} notifications were not delivered");
+        }
+    }
+
+    fn push(&mut self, notification: Notification) {
+        self.pending.push(PendingNotification {
+            notification,
+            sinks: (0..self.notifiers.len()).collect(),
+            attempts: 0,
+            next_attempt_at: Instant::now(),
+        });
+    }
+
+    /// Sends due notifications, or all of them if it's the last attempt before exiting
+    fn deliver(&mut self, last_attempt: bool) {
+        let now = Instant::now();
+        let mut changed = false;
+
+        for pending in &mut self.pending {
+            if !last_attempt && pending.next_attempt_at > now {
+                continue;
+            }
+
+            changed = true;
+
+            let notifiers = &self.notifiers;
+
+            pending.sinks.retain(|index| {
+                match notifiers.notify_sink(*index, &pending.notification) {
+                    Ok(()) => false,
+                    Err(error) => {
+                        log::error!("Failed to send a notification - {error:?}");
+
+                        true
+                    }
+                }
+            });
+
+            pending.attempts += 1;
+            pending.next_attempt_at = Instant::now() + backoff(pending.attempts);
+        }
+
+        if !changed {
+            return;
+        }
+
+        let mut index = 0;
+
+        while index < self.pending.len() {
+            let pending = &self.pending[index];
+
+            if pending.sinks.is_empty() {
+                self.pending.swap_remove(index);
+            } else if pending.attempts >= MAX_ATTEMPTS {
+                log::error!(
+                    "Giving up on a notification after {} attempts",
+                    pending.attempts
+                );
+
+                let pending = self.pending.swap_remove(index);
+                self.undelivered.push(pending.notification);
+            } else {
+                index += 1;
+            }
+        }
+
+        self.save_spool();
+    }
+
+    /// Takes over the spool files of earlier runs, including those of processes that died
+    fn adopt_spools(&mut self) {
+        let Some(spool) = &self.spool else {
+            return;
+        };
+
+        let adopted = match spool.adopt() {
+            Ok(adopted) => adopted,
+            Err(error) => {
+                log::error!(
+                    "Failed to read notification spools of {:?} - {error:?}",
+                    spool.path
+                );
+                return;
+            }
+        };
+
+        let notifications: Vec<Notification> = adopted
+            .iter()
+            .flat_map(|adopted| adopted.notifications.clone())
+            .collect();
+
+        if !notifications.is_empty() {
+            log::info!(
+                "Retrying {} undelivered notifications of earlier runs",
+                notifications.len()
+            );
+        }
+
+        // the sinks may have changed since, spooled notifications are sent to all of them again
+        for notification in notifications {
+            self.push(notification);
+        }
+
+        // the adopted files are removed only after their notifications are in our own spool
+        self.save_spool();
+
+        for adopted in adopted {
+            adopted.remove();
+        }
+    }
+
+    /// Rewrites the spool file with the notifications that are not delivered yet
+    fn save_spool(&self) {
+        let Some(spool) = &self.spool else {
+            return;
+        };
+
+        let notifications: Vec<&Notification> = self
+            .pending
+            .iter()
+            .map(|pending| &pending.notification)
+            .chain(&self.undelivered)
+            .collect();
+
+        if let Err(error) = spool.write(&notifications) {
+            log::error!(
+                "Failed to write notification spool {:?} - {error:?}",
+                spool.path
+            );
+        }
+    }
+}
+
+impl Spool {
+    /// Locks the spool file of the process, the lock is held until the spool is dropped
+    fn open(base_path: &Path, process_id: u32) -> io::Result<Self> {
+        let path = process_spool_path(base_path, process_id);
+        let lock = open_lock(&path)?;
+
+        lock.try_lock().map_err(io::Error::from)?;
+
+        Ok(Self {
+            base_path: base_path.to_path_buf(),
+            path,
+            lock,
+        })
+    }
+
+    /// Reads the spool of this process and every spool file of the base path that isn't locked
+    /// by a running process
+    fn adopt(&self) -> io::Result<Vec<AdoptedSpool>> {
+        // a spool left by an earlier process with the same id is already ours
+        let mut adopted = vec![AdoptedSpool {
+            path: self.path.clone(),
+            lock: None,
+            notifications: read_spool(&self.path)?,
+        }];
+
+        let directory = match self.base_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+
+            // processes that exited before writing a spool leave just the lock file
+            let path = match path.to_str().and_then(|path| path.strip_suffix(".lock")) {
+                Some(spool_path) if Path::new(spool_path).exists() => continue,
+                Some(spool_path) => PathBuf::from(spool_path),
+                None => path,
+            };
+
+            if path == self.path || !is_spool_of(&self.base_path, &path) {
+                continue;
+            }
+
+            let lock = open_lock(&path)?;
+
+            match lock.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => continue,
+                Err(TryLockError::Error(error)) => return Err(error),
+            }
+
+            adopted.push(AdoptedSpool {
+                notifications: read_spool(&path)?,
+                path,
+                lock: Some(lock),
+            });
+        }
+
+        Ok(adopted)
+    }
+
+    /// Replaces the spool file, or removes it if nothing is left
+    fn write(&self, notifications: &[&Notification]) -> io::Result<()> {
+        if notifications.is_empty() {
+            return remove_if_exists(&self.path);
+        }
+
+        write_spool(&self.path, notifications)
+    }
+}
+
+impl Drop for Spool {
+    /// Removes the lock file if nothing is spooled, a left spool stays locked until it's unlocked
+    fn drop(&mut self) {
+        if !self.path.exists() {
+            if let Err(error) = remove_if_exists(&lock_path(&self.path)) {
+                log::error!("Failed to remove notification spool lock - {error:?}");
+            }
+        }
+
+        if let Err(error) = self.lock.unlock() {
+            log::error!("Failed to unlock notification spool - {error:?}");
+        }
+    }
+}
+
+impl AdoptedSpool {
+    fn remove(self) {
+        // the spool of this process is rewritten, not removed
+        let Some(lock) = self.lock else {
+            return;
+        };
+
+        let result =
+            remove_if_exists(&self.path).and_then(|_| remove_if_exists(&lock_path(&self.path)));
+
+        if let Err(error) = result {
+            log::error!(
+                "Failed to remove notification spool {:?} - {error:?}",
+                self.path
+            );
+        }
+
+        drop(lock);
+    }
+}
+
+/// `undelivered_notifications.jsonl` of process 42 is `undelivered_notifications.42.jsonl`
+fn process_spool_path(base_path: &Path, process_id: u32) -> PathBuf {
+    let stem = base_path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let file_name = match base_path.extension() {
+        Some(extension) => format!("{stem}.{process_id}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{process_id}"),
+    };
+
+    base_path.with_file_name(file_name)
+}
+
+/// Matches the spool files of all processes and the base path itself
+fn is_spool_of(base_path: &Path, path: &Path) -> bool {
+    let (Some(base_name), Some(name)) = (base_path.file_name(), path.file_name()) else {
+        return false;
+    };
+
+    if name == base_name {
+        return true;
+    }
+
+    let stem = base_path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = name.to_string_lossy();
+
+    let Some(rest) = name.strip_prefix(&format!("{stem}.")) else {
+        return false;
+    };
+
+    let process_id = match base_path.extension() {
+        Some(extension) => rest.strip_suffix(&format!(".{}", extension.to_string_lossy())),
+        None => Some(rest),
+    };
+
+    process_id.is_some_and(|process_id| process_id.parse::<u32>().is_ok())
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".lock");
+
+    path.with_file_name(file_name)
+}
+
+fn open_lock(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(path))
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+fn read_spool(path: &Path) -> io::Result<Vec<Notification>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+
+    let notifications = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(notification) => Some(notification),
+            Err(error) => {
+                log::error!("Skipping invalid spooled notification - {error:?}");
+                None
+            }
+        })
+        .collect();
+
+    Ok(notifications)
+}
+
+fn write_spool(path: &Path, notifications: &[&Notification]) -> io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+    let mut file = File::create(&temporary_path)?;
+
+    for notification in notifications {
+        let line = serde_json::to_string(notification).unwrap();
+
+        writeln!(file, "{line}")?;
+    }
+
+    file.sync_all()?;
+
+    fs::rename(temporary_path, path)
+}
+
+fn backoff(attempts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::NotificationKind;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    /// Fails the first `failures` notifications, records the ids of the delivered ones
+    #[derive(Clone, Default)]
+    struct StubNotifier {
+        failures: Arc<AtomicU32>,
+        attempts: Arc<AtomicU32>,
+        delivered: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl StubNotifier {
+        fn failing(failures: u32) -> Self {
+            let notifier = Self::default();
+            notifier.failures.store(failures, Ordering::SeqCst);
+
+            notifier
+        }
+    }
+
+    impl Notifier for StubNotifier {
+        fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+
+            let failed = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok();
+
+            if failed {
+                return Err(NotifierError::Failed(1));
+            }
+
+            self.delivered.lock().unwrap().push(notification.id.clone());
+
+            Ok(())
+        }
+    }
+
+    fn notification(id: &str) -> Notification {
+        Notification {
+            id: id.to_string(),
+            kind: NotificationKind::FoundAddress,
+            message: format!("Found a new address {id}"),
+            address: Some(id.to_string()),
+            derivation_path: None,
+            script_type: None,
+            timestamp: 0,
+        }
+    }
+
+    fn notifiers(notifier: &StubNotifier) -> Notifiers {
+        let mut notifiers = Notifiers::default();
+        notifiers.add(notifier.clone());
+
+        notifiers
+    }
+
+    /// Fresh directory for the spool files of a test
+    fn spool_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "foogold-notification-queue-{}-{name}",
+            process::id()
+        ));
+
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn spooled_ids(path: &Path) -> Vec<String> {
+        read_spool(path)
+            .unwrap()
+            .into_iter()
+            .map(|notification| notification.id)
+            .collect()
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(2), Duration::from_secs(2));
+        assert_eq!(backoff(5), Duration::from_secs(16));
+        assert_eq!(backoff(10), Duration::from_secs(512));
+        assert_eq!(backoff(11), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn retries_failed_sinks_only() {
+        let failing = StubNotifier::failing(1);
+        let working = StubNotifier::default();
+
+        let mut notifiers = notifiers(&failing);
+        notifiers.add(working.clone());
+
+        let mut worker = Worker::new(notifiers, None);
+        worker.push(notification("a"));
+        worker.deliver(false);
+
+        assert_eq!(worker.pending.len(), 1);
+        assert_eq!(worker.pending[0].sinks, vec![0]);
+        assert!(worker.pending[0].next_attempt_at > Instant::now());
+
+        // not due yet
+        worker.deliver(false);
+        assert_eq!(failing.attempts.load(Ordering::SeqCst), 1);
+
+        worker.deliver(true);
+
+        assert!(worker.pending.is_empty());
+        assert_eq!(*failing.delivered.lock().unwrap(), vec!["a"]);
+        assert_eq!(*working.delivered.lock().unwrap(), vec!["a"]);
+        assert_eq!(working.attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts_and_keeps_the_spool() {
+        let directory = spool_directory("give-up");
+        let base_path = directory.join("undelivered.jsonl");
+        let notifier = StubNotifier::failing(u32::MAX);
+
+        let spool = Spool::open(&base_path, 1).unwrap();
+        let spool_path = spool.path.clone();
+        let mut worker = Worker::new(notifiers(&notifier), Some(spool));
+        worker.push(notification("a"));
+
+        for _ in 0..MAX_ATTEMPTS - 1 {
+            worker.deliver(true);
+        }
+
+        assert_eq!(worker.pending.len(), 1);
+        assert_eq!(spooled_ids(&spool_path), vec!["a"]);
+
+        worker.deliver(true);
+
+        assert!(worker.pending.is_empty());
+        assert_eq!(worker.undelivered.len(), 1);
+        assert_eq!(notifier.attempts.load(Ordering::SeqCst), MAX_ATTEMPTS);
+        assert_eq!(spooled_ids(&spool_path), vec!["a"]);
+
+        drop(worker);
+
+        assert_eq!(spool_path, directory.join("undelivered.1.jsonl"));
+        assert_eq!(spooled_ids(&spool_path), vec!["a"]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn adopts_spools_that_are_not_locked() {
+        let directory = spool_directory("adopt");
+        let base_path = directory.join("undelivered.jsonl");
+
+        let mut stopped = Worker::new(
+            notifiers(&StubNotifier::failing(u32::MAX)),
+            Some(Spool::open(&base_path, 1).unwrap()),
+        );
+        stopped.push(notification("stopped"));
+        stopped.deliver(true);
+        drop(stopped);
+
+        let mut running = Worker::new(
+            notifiers(&StubNotifier::failing(u32::MAX)),
+            Some(Spool::open(&base_path, 2).unwrap()),
+        );
+        running.push(notification("running"));
+        running.deliver(true);
+
+        // written by an older version without process ids
+        write_spool(&base_path, &[&notification("legacy")]).unwrap();
+
+        // left by a process that exited without a spool
+        open_lock(&directory.join("undelivered.4.jsonl")).unwrap();
+
+        let notifier = StubNotifier::failing(u32::MAX);
+        let mut worker = Worker::new(
+            notifiers(&notifier),
+            Some(Spool::open(&base_path, 3).unwrap()),
+        );
+        worker.adopt_spools();
+
+        let mut ids: Vec<String> = worker
+            .pending
+            .iter()
+            .map(|pending| pending.notification.id.clone())
+            .collect();
+        ids.sort();
+
+        assert_eq!(ids, vec!["legacy", "stopped"]);
+        assert_eq!(
+            spooled_ids(&directory.join("undelivered.3.jsonl")),
+            vec!["legacy", "stopped"]
+        );
+        assert!(!base_path.exists());
+        assert!(!directory.join("undelivered.1.jsonl").exists());
+        assert!(!directory.join("undelivered.1.jsonl.lock").exists());
+        assert!(!directory.join("undelivered.4.jsonl.lock").exists());
+        assert_eq!(
+            spooled_ids(&directory.join("undelivered.2.jsonl")),
+            vec!["running"]
+        );
+
+        drop(running);
+        drop(worker);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn delivers_queued_notifications_on_drop() {
+        let directory = spool_directory("drop");
+        let base_path = directory.join("undelivered.jsonl");
+        let notifier = StubNotifier::failing(1);
+
+        let queue = NotificationQueue::start(notifiers(&notifier), Some(base_path));
+        queue.notify(&notification("a")).unwrap();
+        drop(queue);
+
+        // the first attempt failed, the retry is made on drop without waiting for the backoff
+        assert_eq!(notifier.attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(*notifier.delivered.lock().unwrap(), vec!["a"]);
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 0);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
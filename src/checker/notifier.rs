@@ -0,0 +1,165 @@
+use super::AccountKey;
+use super::Address;
+use serde::Deserialize;
+use serde::Serialize;
+use std::io;
+use std::process;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum NotifierError {
+    #[error("Telegram Error {0:?}")]
+    TelegramError(#[from] frankenstein::Error),
+    #[error("HTTP Error {0:?}")]
+    HttpError(Box<ureq::Error>),
+    #[error("IO Error {0:?}")]
+    IoError(#[from] io::Error),
+    #[error("SMTP Error {0:?}")]
+    SmtpError(#[from] lettre::transport::smtp::Error),
+    #[error("Email Error {0:?}")]
+    EmailError(#[from] lettre::error::Error),
+    #[error("Email Address Error {0:?}")]
+    EmailAddressError(#[from] lettre::address::AddressError),
+    #[error("{0} of the notifiers failed")]
+    Failed(usize),
+    #[error("Notification queue is closed")]
+    QueueClosed,
+}
+
+impl From<ureq::Error> for NotifierError {
+    fn from(error: ureq::Error) -> Self {
+        NotifierError::HttpError(Box::new(error))
+    }
+}
+
+/// Event sent to notifiers, it never contains secrets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Unique per notification and kept for every retry, so sinks can deduplicate deliveries
+    #[serde(default = "notification_id")]
+    pub id: String,
+    pub kind: NotificationKind,
+    pub message: String,
+    pub address: Option<String>,
+    pub derivation_path: Option<String>,
+    pub script_type: Option<String>,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    FoundAddress,
+    FoundAccountKey,
+}
+
+impl Notification {
+    pub fn found_address(address: &Address) -> Self {
+        Self {
+            id: notification_id(),
+            kind: NotificationKind::FoundAddress,
+            message: format!("Found a new address {}", address.address),
+            address: Some(address.address.clone()),
+            derivation_path: Some(address.derivation_path.to_string()),
+            script_type: Some(address.script_type.to_string()),
+            timestamp: now(),
+        }
+    }
+
+    pub fn found_account_key(account_key: &AccountKey) -> Self {
+        Self {
+            id: notification_id(),
+            kind: NotificationKind::FoundAccountKey,
+            message: format!("Found a matching account key {account_key}"),
+            address: None,
+            derivation_path: Some(account_key.derivation_path().to_string()),
+            script_type: None,
+            timestamp: now(),
+        }
+    }
+}
+
+/// Sink for found addresses and keys
+pub trait Notifier: Send {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError>;
+}
+
+/// Sends notifications to all configured sinks.
+///
+/// A failing sink doesn't stop the others, the error is returned after all of them were tried.
+#[derive(Default)]
+pub struct Notifiers {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn add(&mut self, notifier: impl Notifier + 'static) {
+        self.notifiers.push(Box::new(notifier));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifiers.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notifiers.len()
+    }
+
+    /// Sends a notification to the sink at `index` only
+    pub fn notify_sink(
+        &self,
+        index: usize,
+        notification: &Notification,
+    ) -> Result<(), NotifierError> {
+        self.notifiers[index].notify(notification)
+    }
+}
+
+impl Notifier for Notifiers {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+        let mut failed = 0;
+
+        for index in 0..self.len() {
+            if let Err(error) = self.notify_sink(index, notification) {
+                log::error!("Failed to send a notification - {error:?}");
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(NotifierError::Failed(failed));
+        }
+
+        Ok(())
+    }
+}
+
+/// HTTP client of the webhook and Matrix notifiers
+pub(super) fn notifier_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(10))
+        .timeout_read(Duration::from_secs(30))
+        .timeout_write(Duration::from_secs(30))
+        .build()
+}
+
+fn notification_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("foogold-{}-{}-{counter}", now(), process::id())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -1,17 +1,18 @@
-use super::check_address;
+use super::check_candidate;
 use super::AddressGenerator;
 use super::CheckerError;
 use super::MnemonicGenerator;
-use super::TelegramClient;
-use crate::Repo;
+use super::NotificationQueue;
+use super::Secret;
+use super::Target;
 use typed_builder::TypedBuilder;
 
 #[derive(TypedBuilder)]
 pub struct RandomChecker {
     address_generator: AddressGenerator,
-    telegram_client: Option<TelegramClient>,
+    notifier: NotificationQueue,
     mnemonic_generator: MnemonicGenerator,
-    repo: Repo,
+    target: Target,
 }
 
 impl RandomChecker {
@@ -19,13 +20,14 @@ impl RandomChecker {
         loop {
             let mnemonic = self.mnemonic_generator.generate();
 
-            log::info!("Checking mnemonic {mnemonic}");
+            log::info!("Checking mnemonic {}", Secret::from(&mnemonic));
 
-            let addresses = self.address_generator.generate(mnemonic);
-
-            for address in addresses {
-                check_address(&self.repo, &address, &self.telegram_client)?;
-            }
+            check_candidate(
+                &self.target,
+                &self.address_generator,
+                mnemonic,
+                &self.notifier,
+            )?;
         }
     }
 }
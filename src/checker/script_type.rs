@@ -0,0 +1,81 @@
+use super::MultisigScriptType;
+use bitcoin::network::constants::Network;
+use bitcoin::secp256k1;
+use bitcoin::secp256k1::All;
+use bitcoin::util::address::Address as BitcoinAddress;
+use bitcoin::util::bip32::ChildNumber;
+use bitcoin::util::bip32::DerivationPath;
+use bitcoin::util::key::PublicKey;
+use bitcoin::util::key::Secp256k1;
+use std::fmt;
+
+/// Script an address is built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    P2pkh,
+    /// P2PKH of the uncompressed public key, used by early wallets
+    P2pkhUncompressed,
+    P2shP2wpkh,
+    P2wpkh,
+    P2tr,
+    /// Built by `Multisig` together with cosigner keys
+    Multisig(MultisigScriptType),
+}
+
+impl ScriptType {
+    /// Script type of the standard derivation scheme (BIP-44, BIP-49, BIP-84, BIP-86) the path belongs to
+    pub fn from_derivation_path(path: &DerivationPath) -> Option<ScriptType> {
+        match path.into_iter().next() {
+            Some(ChildNumber::Hardened { index: 44 }) => Some(ScriptType::P2pkh),
+            Some(ChildNumber::Hardened { index: 49 }) => Some(ScriptType::P2shP2wpkh),
+            Some(ChildNumber::Hardened { index: 84 }) => Some(ScriptType::P2wpkh),
+            Some(ChildNumber::Hardened { index: 86 }) => Some(ScriptType::P2tr),
+            _ => None,
+        }
+    }
+
+    pub fn address(
+        &self,
+        secp256k1: &Secp256k1<All>,
+        public_key: secp256k1::PublicKey,
+    ) -> BitcoinAddress {
+        let public_key = PublicKey::new(public_key);
+
+        match self {
+            ScriptType::P2pkh => BitcoinAddress::p2pkh(&public_key, Network::Bitcoin),
+            ScriptType::P2pkhUncompressed => {
+                let public_key = PublicKey {
+                    compressed: false,
+                    inner: public_key.inner,
+                };
+
+                BitcoinAddress::p2pkh(&public_key, Network::Bitcoin)
+            }
+            ScriptType::P2shP2wpkh => {
+                BitcoinAddress::p2shwpkh(&public_key, Network::Bitcoin).unwrap()
+            }
+            ScriptType::P2wpkh => BitcoinAddress::p2wpkh(&public_key, Network::Bitcoin).unwrap(),
+            ScriptType::P2tr => {
+                let (internal_key, _parity) = public_key.inner.x_only_public_key();
+
+                BitcoinAddress::p2tr(secp256k1, internal_key, None, Network::Bitcoin)
+            }
+            ScriptType::Multisig(_) => panic!("Multisig addresses require cosigner keys"),
+        }
+    }
+}
+
+impl fmt::Display for ScriptType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ScriptType::P2pkh => "p2pkh",
+            ScriptType::P2pkhUncompressed => "p2pkh-uncompressed",
+            ScriptType::P2shP2wpkh => "p2sh-p2wpkh",
+            ScriptType::P2wpkh => "p2wpkh",
+            ScriptType::P2tr => "p2tr",
+            ScriptType::Multisig(script_type) => return write!(f, "{script_type}-multisig"),
+        };
+
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,88 @@
+use bip39::Mnemonic;
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use zeroize::Zeroize;
+use zeroize::ZeroizeOnDrop;
+
+/// Bytes of the SHA-256 hash shown as the fingerprint of a secret
+const FINGERPRINT_SIZE: usize = 4;
+
+static LOG_SECRETS: AtomicBool = AtomicBool::new(false);
+
+/// Shows secrets themselves instead of their fingerprints in logs and Debug output
+pub fn set_log_secrets(log_secrets: bool) {
+    LOG_SECRETS.store(log_secrets, Ordering::Relaxed);
+}
+
+/// Recovered secret like a mnemonic, zeroized on drop.
+///
+/// Formatted as a fingerprint (`secret:1a2b3c4d`) unless secret logging is enabled, use
+/// [`Secret::expose`] to get the secret itself.
+#[derive(Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Secret(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret(secret)
+    }
+}
+
+impl From<&Mnemonic> for Secret {
+    fn from(mnemonic: &Mnemonic) -> Self {
+        Secret(mnemonic.to_string())
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Redacted(&self.0).fmt(f)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Redacted(&self.0).fmt(f)
+    }
+}
+
+/// Formats a borrowed secret the same way as [`Secret`]
+pub struct Redacted<'a>(pub &'a str);
+
+impl Redacted<'_> {
+    /// Fingerprint of the secret even if secret logging is enabled, for output that isn't a log
+    pub fn fingerprint(&self) -> String {
+        let hash = sha256::Hash::hash(self.0.as_bytes());
+
+        format!("secret:{}", hash[..FINGERPRINT_SIZE].to_hex())
+    }
+}
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_SECRETS.load(Ordering::Relaxed) {
+            return f.write_str(self.0);
+        }
+
+        f.write_str(&self.fingerprint())
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{self}\"")
+    }
+}
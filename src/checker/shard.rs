@@ -0,0 +1,91 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ShardError {
+    #[error("Shard count must be greater than zero")]
+    ZeroCount,
+    #[error("Shard index {index} must be less than shard count {count}")]
+    IndexOutOfRange { index: usize, count: usize },
+}
+
+/// Slice of an enumerated search space.
+///
+/// Candidate `position` belongs to the shard when `position % count == index`, so shards with
+/// the same `count` never overlap and together cover the whole space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    pub fn new(index: usize, count: usize) -> Result<Self, ShardError> {
+        if count == 0 {
+            return Err(ShardError::ZeroCount);
+        }
+
+        if index >= count {
+            return Err(ShardError::IndexOutOfRange { index, count });
+        }
+
+        Ok(Shard { index, count })
+    }
+
+    pub fn contains(&self, position: usize) -> bool {
+        position % self.count == self.index
+    }
+
+    /// Number of candidates of the shard in a search space of `total` candidates
+    pub fn size(&self, total: usize) -> usize {
+        if self.index >= total {
+            return 0;
+        }
+
+        (total - 1 - self.index) / self.count + 1
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Shard { index: 0, count: 1 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_counts_positions_in_the_shard() {
+        for count in 1..5 {
+            for index in 0..count {
+                let shard = Shard::new(index, count).unwrap();
+
+                for total in 0..20 {
+                    let expected = (0..total)
+                        .filter(|position| shard.contains(*position))
+                        .count();
+
+                    assert_eq!(shard.size(total), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_shards() {
+        assert!(matches!(Shard::new(0, 0), Err(ShardError::ZeroCount)));
+        assert!(matches!(
+            Shard::new(2, 2),
+            Err(ShardError::IndexOutOfRange { index: 2, count: 2 })
+        ));
+    }
+}
@@ -0,0 +1,715 @@
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
+use bitcoin::hashes::Hmac;
+use bitcoin::hashes::HmacEngine;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use thiserror::Error;
+
+const RADIX: usize = 1024;
+const RADIX_BITS: usize = 10;
+const HEADER_WORDS: usize = 4;
+const CHECKSUM_WORDS: usize = 3;
+const MIN_SECRET_BITS: usize = 128;
+const MIN_MNEMONIC_WORDS: usize =
+    HEADER_WORDS + MIN_SECRET_BITS.div_ceil(RADIX_BITS) + CHECKSUM_WORDS;
+const MAX_UNKNOWN_WORDS: usize = 2;
+const UNKNOWN_WORD: &str = "?";
+
+const CUSTOMIZATION: &[u8] = b"shamir";
+const EXTENDABLE_CUSTOMIZATION: &[u8] = b"shamir_extendable";
+const CHECKSUM_GENERATOR: [u32; 10] = [
+    0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009, 0x1C0C2412, 0x38086C24, 0x3090FC48,
+    0x21B1F890, 0x3F3F120,
+];
+
+const SECRET_INDEX: u8 = 255;
+const DIGEST_INDEX: u8 = 254;
+const DIGEST_BYTES: usize = 4;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+const ROUND_COUNT: u8 = 4;
+
+/// Official SLIP-39 wordlist, SLIP-39 has no other languages
+const ENGLISH_WORDS: &str = include_str!("slip39_wordlist.txt");
+
+static ENGLISH: Lazy<Slip39Wordlist> = Lazy::new(|| {
+    let words = ENGLISH_WORDS.lines().map(String::from).collect();
+
+    Slip39Wordlist::new(words).expect("Embedded SLIP-39 wordlist is invalid")
+});
+
+/// Exponent and logarithm tables of GF(256) with the Rijndael polynomial and generator 3
+static GF256_TABLES: Lazy<([u8; 255], [u8; 256])> = Lazy::new(|| {
+    let mut exp = [0; 255];
+    let mut log = [0; 256];
+    let mut poly: u16 = 1;
+
+    for (power, value) in exp.iter_mut().enumerate() {
+        *value = poly as u8;
+        log[poly as usize] = power as u8;
+
+        poly = (poly << 1) ^ poly;
+
+        if poly & 0x100 != 0 {
+            poly ^= 0x11B;
+        }
+    }
+
+    (exp, log)
+});
+
+#[derive(Error, Debug)]
+pub enum Slip39Error {
+    #[error("Invalid wordlist, {0}")]
+    InvalidWordlist(String),
+    #[error("Invalid share length {0}")]
+    InvalidLength(usize),
+    #[error("Too many unknown words {0}, up to {MAX_UNKNOWN_WORDS} can be recovered")]
+    TooManyUnknownWords(usize),
+    #[error("Invalid share {0}")]
+    InvalidShare(String),
+    #[error("Shares don't belong to the same secret, {0}")]
+    MismatchedShares(String),
+    #[error("Not enough shares, {0}")]
+    InsufficientShares(String),
+    #[error("Invalid digest of the shared secret")]
+    InvalidDigest,
+}
+
+/// SLIP-39 wordlist, 1024 unique words in alphabetical order
+#[derive(Debug, Clone)]
+pub struct Slip39Wordlist {
+    words: Vec<String>,
+    indexes: HashMap<String, u16>,
+}
+
+impl Slip39Wordlist {
+    pub fn new(words: Vec<String>) -> Result<Self, Slip39Error> {
+        if words.len() != RADIX {
+            return Err(Slip39Error::InvalidWordlist(format!(
+                "expected {RADIX} words, got {}",
+                words.len()
+            )));
+        }
+
+        if let Some((previous, word)) = words.iter().tuple_windows().find(|(a, b)| a >= b) {
+            return Err(Slip39Error::InvalidWordlist(format!(
+                "words must be sorted and unique, {word} follows {previous}"
+            )));
+        }
+
+        let indexes = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| (word.clone(), index as u16))
+            .collect();
+
+        Ok(Self { words, indexes })
+    }
+
+    pub fn english() -> &'static Self {
+        &ENGLISH
+    }
+
+    fn index(&self, word: &str) -> Option<u16> {
+        self.indexes.get(&word.to_lowercase()).copied()
+    }
+
+    fn mnemonic(&self, indexes: &[u16]) -> String {
+        indexes
+            .iter()
+            .map(|index| self.words[*index as usize].as_str())
+            .join(" ")
+    }
+}
+
+/// Single SLIP-39 share.
+///
+/// Shares are recovered from their mnemonics with [`Slip39Share::recover`], which also fills in
+/// unknown (`?`), unreadable or missing words using the share checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slip39Share {
+    mnemonic: String,
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Vec<u8>,
+}
+
+impl Slip39Share {
+    /// All shares matching `raw_share` with a valid checksum.
+    ///
+    /// Words that are `?` or not in the wordlist are treated as unknown. When the share is one
+    /// word short, the missing word is tried at every position.
+    pub fn recover(
+        raw_share: &str,
+        wordlist: &Slip39Wordlist,
+    ) -> Result<Vec<Slip39Share>, Slip39Error> {
+        let words: Vec<Option<u16>> = raw_share
+            .split_whitespace()
+            .enumerate()
+            .map(|(position, word)| {
+                let index = wordlist.index(word);
+
+                if index.is_none() && word != UNKNOWN_WORD {
+                    log::warn!(
+                        "Unknown SLIP-39 word at position {}, trying all words in its place",
+                        position + 1
+                    );
+                }
+
+                index
+            })
+            .collect();
+
+        let templates = if is_valid_length(words.len()) {
+            vec![words]
+        } else if is_valid_length(words.len() + 1) && words.iter().all(Option::is_some) {
+            (0..=words.len())
+                .map(|position| {
+                    let mut template = words.clone();
+                    template.insert(position, None);
+                    template
+                })
+                .collect()
+        } else {
+            return Err(Slip39Error::InvalidLength(words.len()));
+        };
+
+        let mut shares = vec![];
+
+        for template in templates {
+            for indexes in complete(&template)? {
+                let share = Slip39Share::from_indexes(&indexes, wordlist)?;
+
+                if !shares.contains(&share) {
+                    shares.push(share);
+                }
+            }
+        }
+
+        if shares.is_empty() {
+            return Err(Slip39Error::InvalidShare(format!(
+                "no valid checksum for {raw_share}"
+            )));
+        }
+
+        Ok(shares)
+    }
+
+    /// Share with all words known and a valid checksum, nothing is recovered
+    pub fn parse(mnemonic: &str, wordlist: &Slip39Wordlist) -> Result<Slip39Share, Slip39Error> {
+        let indexes: Vec<u16> = mnemonic
+            .split_whitespace()
+            .map(|word| wordlist.index(word))
+            .collect::<Option<_>>()
+            .ok_or_else(|| Slip39Error::InvalidShare("unknown word".to_string()))?;
+
+        if !is_valid_length(indexes.len()) {
+            return Err(Slip39Error::InvalidLength(indexes.len()));
+        }
+
+        if !verify_checksum(&indexes) {
+            return Err(Slip39Error::InvalidShare("invalid checksum".to_string()));
+        }
+
+        Slip39Share::from_indexes(&indexes, wordlist)
+    }
+
+    /// Combines shares into the master secret, `passphrase` is the optional SLIP-39 passphrase
+    pub fn combine(shares: &[Slip39Share], passphrase: &str) -> Result<Vec<u8>, Slip39Error> {
+        let first = shares.first().ok_or_else(|| {
+            Slip39Error::InsufficientShares("at least one share is required".to_string())
+        })?;
+
+        for share in shares {
+            if share.identifier != first.identifier
+                || share.extendable != first.extendable
+                || share.iteration_exponent != first.iteration_exponent
+            {
+                return Err(Slip39Error::MismatchedShares(
+                    "identifiers or iteration exponents differ".to_string(),
+                ));
+            }
+
+            if share.group_threshold != first.group_threshold
+                || share.group_count != first.group_count
+            {
+                return Err(Slip39Error::MismatchedShares(
+                    "group parameters differ".to_string(),
+                ));
+            }
+
+            if share.value.len() != first.value.len() {
+                return Err(Slip39Error::MismatchedShares(
+                    "share lengths differ".to_string(),
+                ));
+            }
+        }
+
+        let mut groups: BTreeMap<u8, Vec<&Slip39Share>> = BTreeMap::new();
+
+        for share in shares {
+            let members = groups.entry(share.group_index).or_default();
+
+            if members
+                .iter()
+                .any(|member| member.member_threshold != share.member_threshold)
+            {
+                return Err(Slip39Error::MismatchedShares(format!(
+                    "member thresholds differ in group {}",
+                    share.group_index
+                )));
+            }
+
+            if !members
+                .iter()
+                .any(|member| member.member_index == share.member_index)
+            {
+                members.push(share);
+            }
+        }
+
+        let mut group_secrets = vec![];
+
+        for (group_index, members) in &groups {
+            let threshold = members[0].member_threshold as usize;
+
+            if members.len() < threshold {
+                log::warn!(
+                    "Group {group_index} has {} of {threshold} required shares",
+                    members.len()
+                );
+                continue;
+            }
+
+            let points: Vec<(u8, Vec<u8>)> = members[..threshold]
+                .iter()
+                .map(|member| (member.member_index, member.value.clone()))
+                .collect();
+
+            group_secrets.push((*group_index, recover_secret(&points)?));
+        }
+
+        let group_threshold = first.group_threshold as usize;
+
+        if group_secrets.len() < group_threshold {
+            return Err(Slip39Error::InsufficientShares(format!(
+                "{} of {group_threshold} required groups are complete",
+                group_secrets.len()
+            )));
+        }
+
+        let encrypted_secret = recover_secret(&group_secrets[..group_threshold])?;
+
+        Ok(decrypt(
+            &encrypted_secret,
+            passphrase.as_bytes(),
+            first.iteration_exponent,
+            first.identifier,
+            first.extendable,
+        ))
+    }
+
+    pub fn mnemonic(&self) -> &str {
+        &self.mnemonic
+    }
+
+    /// Size of the share value, the size of the master secret
+    pub fn secret_bits(&self) -> usize {
+        self.value.len() * 8
+    }
+
+    fn from_indexes(
+        indexes: &[u16],
+        wordlist: &Slip39Wordlist,
+    ) -> Result<Slip39Share, Slip39Error> {
+        let mnemonic = wordlist.mnemonic(indexes);
+        let words: Vec<u32> = indexes.iter().map(|index| *index as u32).collect();
+
+        let identifier = ((words[0] << 5) | (words[1] >> 5)) as u16;
+        let extendable = (words[1] >> 4) & 1 == 1;
+        let iteration_exponent = (words[1] & 0xF) as u8;
+
+        let parameters = (words[2] << 10) | words[3];
+        let group_index = ((parameters >> 16) & 0xF) as u8;
+        let group_threshold = ((parameters >> 12) & 0xF) as u8 + 1;
+        let group_count = ((parameters >> 8) & 0xF) as u8 + 1;
+        let member_index = ((parameters >> 4) & 0xF) as u8;
+        let member_threshold = (parameters & 0xF) as u8 + 1;
+
+        if group_threshold > group_count {
+            return Err(Slip39Error::InvalidShare(format!(
+                "group threshold {group_threshold} exceeds group count {group_count} in {mnemonic}"
+            )));
+        }
+
+        let value_words = &words[HEADER_WORDS..words.len() - CHECKSUM_WORDS];
+        let padding = value_words.len() * RADIX_BITS % 16;
+
+        let bits: Vec<bool> = value_words
+            .iter()
+            .flat_map(|word| (0..RADIX_BITS).rev().map(move |bit| (word >> bit) & 1 == 1))
+            .collect();
+
+        if bits[..padding].iter().any(|bit| *bit) {
+            return Err(Slip39Error::InvalidShare(format!(
+                "invalid padding in {mnemonic}"
+            )));
+        }
+
+        let value = bits[padding..]
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+            .collect();
+
+        Ok(Slip39Share {
+            mnemonic,
+            identifier,
+            extendable,
+            iteration_exponent,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index,
+            member_threshold,
+            value,
+        })
+    }
+}
+
+fn is_valid_length(word_count: usize) -> bool {
+    word_count >= MIN_MNEMONIC_WORDS
+        && (word_count - HEADER_WORDS - CHECKSUM_WORDS) * RADIX_BITS % 16 <= 8
+}
+
+/// All completions of unknown words that have a valid checksum
+fn complete(template: &[Option<u16>]) -> Result<Vec<Vec<u16>>, Slip39Error> {
+    let unknown: Vec<usize> = template.iter().positions(|word| word.is_none()).collect();
+
+    if unknown.len() > MAX_UNKNOWN_WORDS {
+        return Err(Slip39Error::TooManyUnknownWords(unknown.len()));
+    }
+
+    let mut indexes: Vec<u16> = template.iter().map(|word| word.unwrap_or(0)).collect();
+
+    if unknown.is_empty() {
+        return Ok(if verify_checksum(&indexes) {
+            vec![indexes]
+        } else {
+            vec![]
+        });
+    }
+
+    let mut completions = vec![];
+
+    for words in unknown
+        .iter()
+        .map(|_| 0..RADIX as u16)
+        .multi_cartesian_product()
+    {
+        for (position, word) in unknown.iter().zip(words) {
+            indexes[*position] = word;
+        }
+
+        if verify_checksum(&indexes) {
+            completions.push(indexes.clone());
+        }
+    }
+
+    Ok(completions)
+}
+
+fn verify_checksum(indexes: &[u16]) -> bool {
+    let customization = if (indexes[1] >> 4) & 1 == 1 {
+        EXTENDABLE_CUSTOMIZATION
+    } else {
+        CUSTOMIZATION
+    };
+
+    let values = customization
+        .iter()
+        .map(|byte| *byte as u32)
+        .chain(indexes.iter().map(|index| *index as u32));
+
+    polymod(values) == 1
+}
+
+/// Reed-Solomon code over GF(1024) used for share checksums
+fn polymod(values: impl Iterator<Item = u32>) -> u32 {
+    let mut checksum = 1;
+
+    for value in values {
+        let top = checksum >> 20;
+        checksum = ((checksum & 0xFFFFF) << 10) ^ value;
+
+        for (bit, generator) in CHECKSUM_GENERATOR.iter().enumerate() {
+            if (top >> bit) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+
+    checksum
+}
+
+/// Recovers the shared secret and verifies its digest, `points` has exactly `threshold` entries
+fn recover_secret(points: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, Slip39Error> {
+    if points.len() == 1 {
+        return Ok(points[0].1.clone());
+    }
+
+    let secret = interpolate(points, SECRET_INDEX);
+    let digest_share = interpolate(points, DIGEST_INDEX);
+    let (digest, random_part) = digest_share.split_at(DIGEST_BYTES);
+
+    let mut engine = HmacEngine::<sha256::Hash>::new(random_part);
+    engine.input(&secret);
+    let expected_digest = Hmac::from_engine(engine).into_inner();
+
+    if digest != &expected_digest[..DIGEST_BYTES] {
+        return Err(Slip39Error::InvalidDigest);
+    }
+
+    Ok(secret)
+}
+
+/// Lagrange interpolation of `points` at `x` over GF(256)
+fn interpolate(points: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = points.iter().find(|(point_x, _)| *point_x == x) {
+        return value.clone();
+    }
+
+    let (exp, log) = &*GF256_TABLES;
+    let log_product: i32 = points
+        .iter()
+        .map(|(point_x, _)| log[(point_x ^ x) as usize] as i32)
+        .sum();
+
+    let mut result = vec![0; points[0].1.len()];
+
+    for (point_x, value) in points {
+        let log_denominator: i32 = points
+            .iter()
+            .filter(|(other_x, _)| other_x != point_x)
+            .map(|(other_x, _)| log[(point_x ^ other_x) as usize] as i32)
+            .sum();
+
+        let log_basis =
+            (log_product - log[(point_x ^ x) as usize] as i32 - log_denominator).rem_euclid(255);
+
+        for (result_byte, byte) in result.iter_mut().zip(value) {
+            if *byte != 0 {
+                *result_byte ^= exp[((log[*byte as usize] as i32 + log_basis) % 255) as usize];
+            }
+        }
+    }
+
+    result
+}
+
+/// Four round Feistel network keyed with the passphrase
+fn decrypt(
+    encrypted_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Vec<u8> {
+    let (left, right) = encrypted_secret.split_at(encrypted_secret.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+
+    let mut salt = vec![];
+
+    if !extendable {
+        salt.extend_from_slice(CUSTOMIZATION);
+        salt.extend_from_slice(&identifier.to_be_bytes());
+    }
+
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / ROUND_COUNT as u32;
+
+    for round in (0..ROUND_COUNT).rev() {
+        let password = [&[round], passphrase].concat();
+        let round_salt = [salt.as_slice(), &right].concat();
+        let round_key = pbkdf2_sha256(&password, &round_salt, iterations, right.len());
+
+        let next_right = left
+            .iter()
+            .zip(round_key)
+            .map(|(byte, key_byte)| byte ^ key_byte)
+            .collect();
+
+        left = right;
+        right = next_right;
+    }
+
+    [right, left].concat()
+}
+
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32, length: usize) -> Vec<u8> {
+    let mut output = vec![];
+
+    for block in 1.. {
+        if output.len() >= length {
+            break;
+        }
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(password);
+        engine.input(salt);
+        engine.input(&(block as u32).to_be_bytes());
+
+        let mut u = Hmac::from_engine(engine).into_inner();
+        let mut t = u;
+
+        for _ in 1..iterations {
+            let mut engine = HmacEngine::<sha256::Hash>::new(password);
+            engine.input(&u);
+            u = Hmac::from_engine(engine).into_inner();
+
+            for (t_byte, u_byte) in t.iter_mut().zip(u) {
+                *t_byte ^= u_byte;
+            }
+        }
+
+        output.extend_from_slice(&t);
+    }
+
+    output.truncate(length);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::hex::ToHex;
+
+    /// Passphrase of the official SLIP-39 test vectors
+    const PASSPHRASE: &str = "TREZOR";
+    const SINGLE_SHARE: &str = "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard";
+
+    fn recover_one(mnemonic: &str) -> Slip39Share {
+        let mut shares = Slip39Share::recover(mnemonic, Slip39Wordlist::english()).unwrap();
+
+        assert_eq!(shares.len(), 1);
+        shares.remove(0)
+    }
+
+    fn combine(mnemonics: &[&str]) -> Result<String, Slip39Error> {
+        let shares: Vec<Slip39Share> = mnemonics
+            .iter()
+            .map(|mnemonic| recover_one(mnemonic))
+            .collect();
+
+        Slip39Share::combine(&shares, PASSPHRASE).map(|secret| secret.to_hex())
+    }
+
+    #[test]
+    fn embedded_wordlist() {
+        let wordlist = Slip39Wordlist::english();
+
+        assert_eq!(wordlist.index("academic"), Some(0));
+        assert_eq!(wordlist.index("zero"), Some(1023));
+    }
+
+    #[test]
+    fn valid_mnemonic_without_sharing_128_bits() {
+        assert_eq!(
+            combine(&[SINGLE_SHARE]).unwrap(),
+            "bb54aac4b89dc868ba37d9cc21b2cece"
+        );
+    }
+
+    #[test]
+    fn mnemonic_with_invalid_checksum() {
+        let result = Slip39Share::recover(
+            "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision kidney",
+            Slip39Wordlist::english(),
+        );
+
+        assert!(matches!(result, Err(Slip39Error::InvalidShare(_))));
+    }
+
+    #[test]
+    fn basic_sharing_2_of_3_128_bits() {
+        let shares = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ];
+
+        assert_eq!(
+            combine(&shares).unwrap(),
+            "b43ceb7e57a0ea8766221624d01b0864"
+        );
+        assert!(matches!(
+            combine(&shares[..1]),
+            Err(Slip39Error::InsufficientShares(_))
+        ));
+    }
+
+    #[test]
+    fn valid_mnemonic_without_sharing_256_bits() {
+        let share = "theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck";
+
+        assert_eq!(
+            combine(&[share]).unwrap(),
+            "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92"
+        );
+    }
+
+    #[test]
+    fn valid_extendable_mnemonic_without_sharing_128_bits() {
+        let share = "testify swimming academic academic column loyalty smear include exotic bedroom exotic wrist lobe cover grief golden smart junior estimate learn";
+
+        assert_eq!(
+            combine(&[share]).unwrap(),
+            "1679b4516e0ee5954351d288a838f45e"
+        );
+    }
+
+    #[test]
+    fn group_sharing_128_bits() {
+        let shares = [
+            "eraser senior decision shadow artist work morning estate greatest pipeline plan ting petition forget hormone flexible general goat admit surface",
+            "eraser senior ceramic snake clay various huge numb argue hesitate auction category timber browser greatest hanger petition script leaf pickup",
+            "eraser senior ceramic shaft dynamic become junior wrist silver peasant force math alto coal amazing segment yelp velvet image paces",
+            "eraser senior ceramic round column hawk trust auction smug shame alive greatest sheriff living perfect corner chest sled fumes adequate",
+            "eraser senior decision smug corner ruin rescue cubic angel tackle skin skunk program roster trash rumor slush angel flea amazing",
+        ];
+
+        assert_eq!(
+            combine(&shares).unwrap(),
+            "7c3397a292a5941682d7a4ae2d898d11"
+        );
+    }
+
+    #[test]
+    fn basic_sharing_2_of_3_256_bits() {
+        let shares = [
+            "wildlife deal beard romp alcohol space mild usual clothes union nuclear testify course research heat listen task location thank hospital slice smell failure fawn helpful priest ambition average recover lecture process dough stadium",
+            "wildlife deal acrobat romp anxiety axis starting require metric flexible geology game drove editor edge screw helpful have huge holy making pitch unknown carve holiday numb glasses survive already tenant adapt goat fangs",
+        ];
+
+        assert_eq!(
+            combine(&shares).unwrap(),
+            "5385577c8cfc6c1a8aa0f7f10ecde0a3318493262591e78b8c14c6686167123b"
+        );
+    }
+
+    #[test]
+    fn recovers_unknown_and_missing_words() {
+        let unknown = SINGLE_SHARE.replace("fridge", "?");
+        assert_eq!(recover_one(&unknown).mnemonic(), SINGLE_SHARE);
+
+        let missing = SINGLE_SHARE.replace("coal ", "");
+        let shares = Slip39Share::recover(&missing, Slip39Wordlist::english()).unwrap();
+        assert!(shares.iter().any(|share| share.mnemonic() == SINGLE_SHARE));
+    }
+}
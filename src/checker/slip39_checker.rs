@@ -0,0 +1,108 @@
+use super::check_master_key;
+use super::check_shard_count;
+use super::report_progress;
+use super::AddressGenerator;
+use super::CheckerError;
+use super::NotificationQueue;
+use super::Redacted;
+use super::Secret;
+use super::Shard;
+use super::Slip39Share;
+use super::Slip39Wordlist;
+use super::Target;
+use super::PROGRESS_INTERVAL;
+use itertools::Itertools;
+use typed_builder::TypedBuilder;
+use zeroize::Zeroizing;
+
+/// Checks the master secret of a set of SLIP-39 shares.
+///
+/// Every share may have up to two unknown words or one missing word, all combinations of
+/// recovered shares that combine into a valid secret are checked. The combinations can be split
+/// into shards like other enumerated searches.
+#[derive(TypedBuilder)]
+pub struct Slip39Checker {
+    address_generator: AddressGenerator,
+    notifier: NotificationQueue,
+    target: Target,
+    shares: Vec<String>,
+    #[builder(default, setter(into))]
+    passphrase: Secret,
+    #[builder(default)]
+    shard: Shard,
+    /// Name under which the progress is stored
+    #[builder(setter(into))]
+    search: String,
+}
+
+impl Slip39Checker {
+    pub fn check(&self) -> Result<(), CheckerError> {
+        check_shard_count(&self.target, &self.search, &self.shard)?;
+
+        let mut candidates = vec![];
+
+        for raw_share in &self.shares {
+            let shares = Slip39Share::recover(raw_share, Slip39Wordlist::english())?;
+
+            if shares.len() > 1 {
+                log::info!(
+                    "Recovered {} candidates for share {}",
+                    shares.len(),
+                    Redacted(raw_share)
+                );
+            }
+
+            candidates.push(shares);
+        }
+
+        let total = self.shard.size(candidates.iter().map(Vec::len).product());
+        let combinations = candidates
+            .into_iter()
+            .multi_cartesian_product()
+            .enumerate()
+            .filter(|(position, _)| self.shard.contains(*position))
+            .map(|(_, shares)| shares);
+
+        let mut master_secrets = vec![];
+
+        for (checked, shares) in combinations.enumerate() {
+            if checked > 0 && checked % PROGRESS_INTERVAL == 0 {
+                report_progress(&self.target, &self.search, &self.shard, checked, total)?;
+            }
+
+            let master_secret = match Slip39Share::combine(&shares, self.passphrase.expose()) {
+                Ok(master_secret) => Zeroizing::new(master_secret),
+                Err(error) => {
+                    log::warn!("Failed to combine shares - {error}");
+                    continue;
+                }
+            };
+
+            if master_secrets.contains(&master_secret) {
+                continue;
+            }
+
+            let secret = Secret::new(shares.iter().map(Slip39Share::mnemonic).join("\n"));
+
+            log::info!("Checking SLIP-39 shares {secret}");
+
+            let master_key = AddressGenerator::master_key_from_seed(&master_secret);
+
+            check_master_key(
+                &self.target,
+                &self.address_generator,
+                &master_key,
+                secret.expose(),
+                &self.notifier,
+            )?;
+
+            master_secrets.push(master_secret);
+        }
+
+        if master_secrets.is_empty() && total > 0 {
+            log::error!("No combination of the shares in the shard produced a master secret");
+        }
+
+        report_progress(&self.target, &self.search, &self.shard, total, total)
+    }
+}
@@ -0,0 +1,67 @@
+use super::AccountKey;
+use super::CheckerError;
+use super::FoundOutput;
+use crate::FoundAddress;
+use crate::Repo;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Set of addresses derived addresses are checked against
+pub enum Target {
+    /// Addresses indexed from the chain by the indexer
+    Index(Repo),
+    /// User supplied addresses kept in memory, doesn't need the database
+    Addresses(HashSet<String>, FoundOutput),
+    /// Account level extended public key, candidates are matched by the key instead of addresses
+    AccountKey(AccountKey, FoundOutput),
+}
+
+impl Target {
+    pub fn addresses<I: IntoIterator<Item = String>>(addresses: I, output: FoundOutput) -> Self {
+        Target::Addresses(addresses.into_iter().collect(), output)
+    }
+
+    /// Reads addresses from a file, one per line. Empty lines and lines starting with `#` are skipped
+    pub fn read_addresses<P: AsRef<Path>>(path: P) -> Result<Vec<String>, std::io::Error> {
+        let content = fs::read_to_string(path)?;
+
+        let addresses = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect();
+
+        Ok(addresses)
+    }
+
+    pub fn contains(&self, address: &str) -> Result<bool, CheckerError> {
+        match self {
+            Target::Index(repo) => Ok(repo.address_exists(address)?),
+            Target::Addresses(addresses, _) => Ok(addresses.contains(address)),
+            Target::AccountKey(..) => Ok(false),
+        }
+    }
+
+    pub fn repo(&self) -> Option<&Repo> {
+        match self {
+            Target::Index(repo) => Some(repo),
+            Target::Addresses(..) | Target::AccountKey(..) => None,
+        }
+    }
+
+    /// Stores a found address in the index, or writes it to the output of offline targets
+    pub fn record_found(&self, found_address: FoundAddress) -> Result<(), CheckerError> {
+        match self {
+            Target::Index(repo) => {
+                repo.insert_found_address(found_address)?;
+            }
+            Target::Addresses(_, output) | Target::AccountKey(_, output) => {
+                output.write(found_address)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,16 @@
+use super::Notification;
+use super::Notifier;
+use super::NotifierError;
 use frankenstein::Api;
 use frankenstein::Error;
 use frankenstein::SendMessageParams;
 use frankenstein::TelegramApi;
+use std::thread;
+use std::time::Duration;
 use typed_builder::TypedBuilder;
 
+const RATE_LIMIT_DELAY: Duration = Duration::from_millis(2_000);
+
 #[derive(TypedBuilder)]
 pub struct TelegramClient {
     api: Api,
@@ -22,3 +29,14 @@ impl TelegramClient {
         Ok(())
     }
 }
+
+impl Notifier for TelegramClient {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+        self.send_notification(notification.message.clone())?;
+
+        // Telegram limits bots to about one message per second in a chat
+        thread::sleep(RATE_LIMIT_DELAY);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use super::notifier::notifier_agent;
+use super::Notification;
+use super::Notifier;
+use super::NotifierError;
+use bitcoin::hashes::hex::ToHex;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
+use bitcoin::hashes::Hmac;
+use bitcoin::hashes::HmacEngine;
+use typed_builder::TypedBuilder;
+
+const SIGNATURE_HEADER: &str = "X-Foogold-Signature";
+
+/// Posts notifications as JSON to an HTTP endpoint.
+///
+/// If a secret is set, the body is signed with HMAC-SHA256 and the signature is sent in the
+/// `X-Foogold-Signature: sha256=<hex>` header.
+#[derive(TypedBuilder)]
+pub struct WebhookNotifier {
+    /// Times out, so an endpoint that doesn't answer is retried by the queue instead of blocking it
+    #[builder(default_code = "notifier_agent()")]
+    request_agent: ureq::Agent,
+    #[builder(setter(into))]
+    url: String,
+    #[builder(default, setter(into))]
+    secret: Option<String>,
+}
+
+impl WebhookNotifier {
+    fn signature(&self, secret: &str, body: &str) -> String {
+        let mut engine = HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+        engine.input(body.as_bytes());
+
+        Hmac::<sha256::Hash>::from_engine(engine).to_hex()
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifierError> {
+        let body = serde_json::to_string(notification).unwrap();
+        let mut request = self
+            .request_agent
+            .post(&self.url)
+            .set("Content-Type", "application/json");
+
+        if let Some(secret) = &self.secret {
+            let signature = format!("sha256={}", self.signature(secret, &body));
+            request = request.set(SIGNATURE_HEADER, &signature);
+        }
+
+        request.send_string(&body)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::NotificationKind;
+    use std::thread;
+    use tiny_http::Response;
+    use tiny_http::Server;
+
+    #[test]
+    fn posts_signed_json() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr());
+
+        let receiver = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+
+            let header = |name: &str| {
+                request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.as_str().as_str().eq_ignore_ascii_case(name))
+                    .map(|header| header.value.to_string())
+            };
+            let signature = header(SIGNATURE_HEADER);
+            let content_type = header("Content-Type");
+
+            request.respond(Response::empty(200)).unwrap();
+
+            (body, signature, content_type)
+        });
+
+        let notification = Notification {
+            id: "foogold-1-2-3".to_string(),
+            kind: NotificationKind::FoundAddress,
+            message: "Found a new address bc1q".to_string(),
+            address: Some("bc1q".to_string()),
+            derivation_path: Some("m/84'/0'/0'/0/0".to_string()),
+            script_type: Some("p2wpkh".to_string()),
+            timestamp: 1679900000,
+        };
+
+        WebhookNotifier::builder()
+            .url(url)
+            .secret(Some("key".to_string()))
+            .build()
+            .notify(&notification)
+            .unwrap();
+
+        let (body, signature, content_type) = receiver.join().unwrap();
+
+        assert_eq!(
+            body,
+            r#"{"id":"foogold-1-2-3","kind":"found_address","message":"Found a new address bc1q","address":"bc1q","derivation_path":"m/84'/0'/0'/0/0","script_type":"p2wpkh","timestamp":1679900000}"#
+        );
+        assert_eq!(content_type.as_deref(), Some("application/json"));
+
+        // HMAC-SHA256 of the body with the key, computed with `openssl dgst -sha256 -hmac key`
+        assert_eq!(
+            signature.as_deref(),
+            Some("sha256=9bbfa681654d6507cf33651e16d8c1b76e4ee86ddaa282650e1e8a9e5660541b")
+        );
+    }
+}
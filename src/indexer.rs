@@ -1,14 +1,45 @@
+use std::fmt;
+use std::ops::Range;
 use thiserror::Error;
 use typed_builder::TypedBuilder;
 
 mod client;
+mod encryption;
+mod found_report;
+mod index_file;
+mod index_status;
+mod lookup_server;
 mod repo;
+mod rpc_auth;
+mod utxo_snapshot;
 
 pub use client::ClientError;
 pub use client::RpcClient;
+pub use encryption::is_encrypted;
+pub use encryption::EncryptionError;
+pub use encryption::SecretDecryptor;
+pub use encryption::SecretEncryptor;
+pub use found_report::format_time;
+pub use found_report::render_found_addresses;
+pub use found_report::OutputFormat;
+pub use index_file::export_index;
+pub use index_file::import_index;
+pub use index_file::verify_index_file;
+pub use index_file::IndexFileHeader;
+pub use index_status::missing_ranges;
+pub use index_status::IndexStatus;
+pub use lookup_server::LookupServer;
+pub use repo::AddressSet;
+pub use repo::FailedBlock;
 pub use repo::FoundAddress;
+pub use repo::FoundAddressRecord;
 pub use repo::Repo;
 pub use repo::RepoError;
+pub use repo::ShardProgress;
+pub use rpc_auth::RpcAuth;
+pub use rpc_auth::RpcAuthError;
+pub use utxo_snapshot::import_utxo_snapshot;
+pub use utxo_snapshot::UtxoSnapshot;
 
 #[derive(Error, Debug)]
 pub enum IndexerError {
@@ -22,29 +53,206 @@ pub enum IndexerError {
 pub struct BlockData {
     block_number: u64,
     addresses: Vec<String>,
+    /// P2PKH addresses of public keys paid to with P2PK outputs
+    p2pk_addresses: Vec<String>,
 }
 
+/// Number of block hashes requested in one batch
+const BLOCK_HASH_BATCH_SIZE: u64 = 100;
+
 #[derive(TypedBuilder)]
 pub struct Indexer {
     client: RpcClient,
     repo: Repo,
+    /// Copy block ranges into the staging tables instead of inserting every block into the index
+    #[builder(default)]
+    bulk: bool,
 }
 
 impl Indexer {
     pub fn index_block(&self, block_number: u64) -> Result<(), IndexerError> {
-        if !self.repo.block_exists(block_number as i32)? {
-            let block_data = self.client.get_block_data_by_block_number(block_number)?;
+        self.index_block_with_hash(block_number, None)
+    }
+
+    fn index_block_with_hash(
+        &self,
+        block_number: u64,
+        block_hash: Option<String>,
+    ) -> Result<(), IndexerError> {
+        match self.fetch_block_data(block_number, block_hash)? {
+            Some(block_data) => {
+                self.repo.insert_block_data(&block_data)?;
+
+                log::info!(
+                    "Block {block_number} indexed, the number of addresses - {}, p2pk - {}",
+                    block_data.addresses.len(),
+                    block_data.p2pk_addresses.len()
+                );
+            }
+            None => log::info!("Block {block_number} already indexed"),
+        }
+
+        Ok(())
+    }
+
+    /// Returns `None` for blocks that are already indexed
+    fn fetch_block_data(
+        &self,
+        block_number: u64,
+        block_hash: Option<String>,
+    ) -> Result<Option<BlockData>, IndexerError> {
+        if self.repo.block_exists(block_number as i32)? {
+            return Ok(None);
+        }
+
+        let block_data = match block_hash {
+            Some(block_hash) => self
+                .client
+                .get_block_data_by_block_hash(block_hash, block_number)?,
+            None => self.client.get_block_data_by_block_number(block_number)?,
+        };
+
+        Ok(Some(block_data))
+    }
+
+    /// Indexes blocks from `start` to `end` (exclusive), failed blocks are recorded to be retried.
+    ///
+    /// Returns the number of failed blocks.
+    pub fn index_blocks(&self, start: u64, end: u64) -> u64 {
+        let mut failed_block_count = 0;
+
+        for batch_start in (start..end).step_by(BLOCK_HASH_BATCH_SIZE as usize) {
+            let batch = batch_start..(batch_start + BLOCK_HASH_BATCH_SIZE).min(end);
+
+            let block_hashes = match self.client.get_block_hashes(batch.clone()) {
+                Ok(block_hashes) => block_hashes.into_iter().map(Some).collect(),
+                Err(error) => {
+                    log::warn!(
+                        "Failed to get hashes of blocks {batch:?}, getting them one by one - {error:?}"
+                    );
+
+                    vec![None; batch.clone().count()]
+                }
+            };
+
+            if self.bulk {
+                failed_block_count += self.stage_blocks(batch, block_hashes);
+                continue;
+            }
+
+            for (block_number, block_hash) in batch.zip(block_hashes) {
+                if let Err(error) = self.index_block_with_hash(block_number, block_hash) {
+                    self.record_failed_block(block_number, &error);
+                    failed_block_count += 1;
+                }
+            }
+        }
+
+        failed_block_count
+    }
 
-            self.repo.insert_block_data(&block_data)?;
+    /// Copies a batch of blocks into the staging tables at once, returns the number of failed blocks
+    fn stage_blocks(&self, batch: Range<u64>, block_hashes: Vec<Option<String>>) -> u64 {
+        let mut block_datas = vec![];
+        let mut failed_block_count = 0;
 
-            log::info!(
-                "Block {block_number} indexed, the number of addresses - {}",
-                block_data.addresses.len()
-            );
-        } else {
-            log::info!("Block {block_number} already indexed");
+        for (block_number, block_hash) in batch.clone().zip(block_hashes) {
+            match self.fetch_block_data(block_number, block_hash) {
+                Ok(Some(block_data)) => block_datas.push(block_data),
+                Ok(None) => log::info!("Block {block_number} already indexed"),
+                Err(error) => {
+                    self.record_failed_block(block_number, &error);
+                    failed_block_count += 1;
+                }
+            }
+        }
+
+        if block_datas.is_empty() {
+            return failed_block_count;
+        }
+
+        match self.repo.copy_block_data(&block_datas) {
+            Ok(()) => log::info!(
+                "Blocks {batch:?} staged, the number of addresses - {}, p2pk - {}",
+                block_datas
+                    .iter()
+                    .map(|block_data| block_data.addresses.len())
+                    .sum::<usize>(),
+                block_datas
+                    .iter()
+                    .map(|block_data| block_data.p2pk_addresses.len())
+                    .sum::<usize>()
+            ),
+            Err(error) => {
+                for block_data in &block_datas {
+                    self.record_failed_block(block_data.block_number, &error);
+                }
+
+                failed_block_count += block_datas.len() as u64;
+            }
+        }
+
+        failed_block_count
+    }
+
+    /// Indexes blocks recorded as failed, they are removed from the failed blocks once indexed
+    pub fn retry_failed_blocks(&self) -> Result<(), IndexerError> {
+        let failed_blocks = self.repo.failed_blocks()?;
+
+        log::info!("Retrying {} failed blocks", failed_blocks.len());
+
+        for failed_block in failed_blocks {
+            let block_number = failed_block.block_number as u64;
+
+            match self.index_block(block_number) {
+                Ok(()) => {
+                    self.repo.delete_failed_block(failed_block.block_number)?;
+                }
+                Err(error) => self.record_failed_block(block_number, &error),
+            }
         }
 
         Ok(())
     }
+
+    /// Indexes blocks missing below `end`, or below the highest indexed block without it.
+    ///
+    /// Blocks staged by bulk indexing are not missing, they are indexed by `index finish-bulk`.
+    ///
+    /// Returns the numbers of filled and failed blocks, failed blocks are recorded to be retried.
+    pub fn fill_gaps(&self, end: Option<u64>) -> Result<(u64, u64), IndexerError> {
+        let indexed_ranges = self.repo.indexed_or_staged_block_ranges()?;
+        let end = end
+            .or_else(|| indexed_ranges.last().map(|range| range.end() + 1))
+            .unwrap_or(0);
+
+        let missing_ranges = missing_ranges(&indexed_ranges, 0..end);
+        let missing_block_count: u64 = missing_ranges
+            .iter()
+            .map(|range| range.end() - range.start() + 1)
+            .sum();
+
+        log::info!(
+            "Filling {missing_block_count} missing blocks in {} gaps",
+            missing_ranges.len()
+        );
+
+        let failed_block_count = missing_ranges
+            .into_iter()
+            .map(|range| self.index_blocks(*range.start(), range.end() + 1))
+            .sum::<u64>();
+
+        Ok((missing_block_count - failed_block_count, failed_block_count))
+    }
+
+    fn record_failed_block<E: fmt::Debug + fmt::Display>(&self, block_number: u64, error: &E) {
+        log::error!("Failed to index the block {block_number} - {error:?}");
+
+        if let Err(repo_error) = self
+            .repo
+            .upsert_failed_block(block_number as i32, &error.to_string())
+        {
+            log::error!("Failed to record the failed block {block_number} - {repo_error:?}");
+        }
+    }
 }
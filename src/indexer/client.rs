@@ -1,7 +1,16 @@
 use super::BlockData;
+use super::RpcAuth;
+use super::RpcAuthError;
+use bitcoin::blockdata::script::Script;
+use bitcoin::hashes::hex::FromHex;
+use bitcoin::network::constants::Network;
+use bitcoin::util::address::Address;
+use bitcoin::util::key::PublicKey;
 use itertools::Itertools;
 use serde::Deserialize;
 use serde::Serialize;
+use std::ops::Range;
+use std::thread;
 use std::time::Duration;
 use thiserror::Error;
 use typed_builder::TypedBuilder;
@@ -15,22 +24,68 @@ pub struct RpcClient {
 
     #[builder(default)]
     header: Option<(String, String)>,
+
+    #[builder(default)]
+    auth: Option<RpcAuth>,
+
+    /// Retries of requests failed with a network error, a 5xx status or while the node warms up
+    #[builder(default = 5)]
+    max_retries: u32,
+
+    /// Delay before the first retry, doubled for every next one
+    #[builder(default_code = "Duration::from_secs(1)")]
+    retry_delay: Duration,
 }
 
-const ADDRESS_KINDS: [AddressKind; 3] = [
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Bitcoin Core error code returned while the node is still loading
+const RPC_IN_WARMUP: i64 = -28;
+
+const ADDRESS_KINDS: [AddressKind; 5] = [
     AddressKind::PubKeyHash,
     AddressKind::WitnessV0Keyhash,
     AddressKind::ScriptHash,
+    AddressKind::WitnessV0Scripthash,
+    AddressKind::Witnessv1Taproot,
 ];
 
 #[derive(Error, Debug)]
 pub enum ClientError {
     #[error("Api Error {0:?}")]
-    Api(#[from] ureq::Error),
+    Api(Box<ureq::Error>),
     #[error("IO Error {0:?}")]
     IoError(#[from] std::io::Error),
     #[error("Decode Error {0:?}")]
     DecodeError(#[from] serde_json::Error),
+    #[error("Auth Error {0:?}")]
+    Auth(#[from] RpcAuthError),
+    #[error("RPC Error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("Response has neither a result nor an error")]
+    MissingResult,
+    #[error("Batch response doesn't match the {0} requests")]
+    BatchMismatch(usize),
+}
+
+impl From<ureq::Error> for ClientError {
+    fn from(error: ureq::Error) -> Self {
+        ClientError::Api(Box::new(error))
+    }
+}
+
+impl ClientError {
+    /// Network errors, server errors and a warming up node are worth retrying, a 401 is not
+    fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Api(error) => match error.as_ref() {
+                ureq::Error::Status(status, _) => *status >= 500,
+                ureq::Error::Transport(_) => true,
+            },
+            ClientError::Rpc { code, .. } => *code == RPC_IN_WARMUP,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, TypedBuilder)]
@@ -38,13 +93,35 @@ struct RpcParams<T: serde::ser::Serialize + std::fmt::Debug> {
     #[builder(setter(into))]
     method: String,
     params: T,
+    /// Matches responses of batch requests to their requests
+    #[builder(default)]
+    id: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 struct Response<T> {
-    result: T,
-    error: Option<String>,
-    id: Option<String>,
+    result: Option<T>,
+    error: Option<RpcError>,
+    id: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl<T> Response<T> {
+    fn into_result(self) -> Result<T, ClientError> {
+        if let Some(error) = self.error {
+            return Err(ClientError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        self.result.ok_or(ClientError::MissingResult)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -66,6 +143,7 @@ struct Output {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 struct ScriptPubKey {
     address: Option<String>,
+    hex: Option<String>,
     #[serde(rename = "type")]
     kind: AddressKind,
 }
@@ -106,6 +184,31 @@ impl RpcClient {
         Ok(block_data)
     }
 
+    pub fn get_block_data_by_block_hash(
+        &self,
+        block_hash: String,
+        block_number: u64,
+    ) -> Result<BlockData, ClientError> {
+        let api_block = self.get_block(block_hash)?;
+        let block_data = self.api_block_to_block_data(api_block, block_number);
+
+        Ok(block_data)
+    }
+
+    /// Height of the node's chain tip
+    pub fn get_block_count(&self) -> Result<u64, ClientError> {
+        let block_count = self.request("getblockcount", [(); 0])?;
+
+        Ok(block_count)
+    }
+
+    /// Gets hashes of a range of blocks in one batch request
+    pub fn get_block_hashes(&self, block_numbers: Range<u64>) -> Result<Vec<String>, ClientError> {
+        let params: Vec<[u64; 1]> = block_numbers.map(|block_number| [block_number]).collect();
+
+        self.batch_request("getblockhash", &params)
+    }
+
     fn get_block_hash(&self, block_number: u64) -> Result<String, ClientError> {
         let block_hash = self.request("getblockhash", [block_number])?;
 
@@ -126,6 +229,83 @@ impl RpcClient {
         method: &str,
         params: T1,
     ) -> Result<T2, ClientError> {
+        let encoded_params = self.params(method, &params);
+        let response: Response<T2> = self.send_with_retries(method, &encoded_params)?;
+
+        response.into_result()
+    }
+
+    /// Sends all calls in one JSON-RPC batch, fails with the first error if any of the calls fails
+    fn batch_request<
+        T1: serde::ser::Serialize + std::fmt::Debug,
+        T2: serde::de::DeserializeOwned + std::fmt::Debug,
+    >(
+        &self,
+        method: &str,
+        params: &[T1],
+    ) -> Result<Vec<T2>, ClientError> {
+        if params.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let batch: Vec<RpcParams<&T1>> = params
+            .iter()
+            .enumerate()
+            .map(|(id, params)| {
+                RpcParams::builder()
+                    .method(method)
+                    .params(params)
+                    .id(id as u64)
+                    .build()
+            })
+            .collect();
+
+        let encoded_params = serde_json::to_string(&batch).unwrap();
+        let mut responses: Vec<Response<T2>> = self.send_with_retries(method, &encoded_params)?;
+
+        // responses of a batch may come in any order
+        responses.sort_by_key(|response| response.id);
+
+        let ids_match = responses.len() == params.len()
+            && responses
+                .iter()
+                .enumerate()
+                .all(|(id, response)| response.id == Some(id as u64));
+
+        if !ids_match {
+            return Err(ClientError::BatchMismatch(params.len()));
+        }
+
+        responses.into_iter().map(Response::into_result).collect()
+    }
+
+    fn send_with_retries<T: serde::de::DeserializeOwned + std::fmt::Debug>(
+        &self,
+        method: &str,
+        encoded_params: &str,
+    ) -> Result<T, ClientError> {
+        let mut retry_delay = self.retry_delay;
+        let mut retries = 0;
+
+        loop {
+            match self.send(encoded_params) {
+                Err(error) if retries < self.max_retries && error.is_transient() => {
+                    log::warn!("Request {method} failed, retrying in {retry_delay:?} - {error:?}");
+
+                    thread::sleep(retry_delay);
+
+                    retries += 1;
+                    retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn send<T: serde::de::DeserializeOwned + std::fmt::Debug>(
+        &self,
+        encoded_params: &str,
+    ) -> Result<T, ClientError> {
         let prepared_request = self
             .request_agent
             .post(&self.url)
@@ -136,12 +316,31 @@ impl RpcClient {
             Some((key, value)) => prepared_request.set(key, value),
         };
 
-        let encoded_params = self.params(method, &params);
-        let response = prepared_request.send_string(&encoded_params)?;
+        let prepared_request = match &self.auth {
+            None => prepared_request,
+            Some(auth) => prepared_request.set("Authorization", &auth.header_value()?),
+        };
 
-        let response: Response<T2> = serde_json::from_reader(response.into_reader())?;
+        let response = match prepared_request.send_string(encoded_params) {
+            Ok(response) => response,
+            // Bitcoin Core answers failed calls with an error status and the JSON-RPC error
+            Err(ureq::Error::Status(_, response))
+                if response.content_type() == "application/json" =>
+            {
+                let response: Response<serde_json::Value> =
+                    serde_json::from_reader(response.into_reader())?;
 
-        Ok(response.result)
+                return match response.into_result() {
+                    Err(error) => Err(error),
+                    Ok(_) => Err(ClientError::MissingResult),
+                };
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        let response = serde_json::from_reader(response.into_reader())?;
+
+        Ok(response)
     }
 
     fn params<T: serde::ser::Serialize + std::fmt::Debug>(
@@ -155,19 +354,47 @@ impl RpcClient {
     }
 
     fn api_block_to_block_data(&self, api_block: Block, block_number: u64) -> BlockData {
-        let addresses = api_block
+        let script_pub_keys = api_block
             .tx
             .into_iter()
             .flat_map(|tx| tx.vout)
             .map(|vout| vout.script_pub_key)
+            .collect::<Vec<ScriptPubKey>>();
+
+        let addresses = script_pub_keys
+            .iter()
             .filter(|script_pub_key| ADDRESS_KINDS.contains(&script_pub_key.kind))
-            .flat_map(|script_pub_key| script_pub_key.address)
+            .flat_map(|script_pub_key| script_pub_key.address.clone())
+            .unique_by(|address| address.clone())
+            .collect::<Vec<String>>();
+
+        let p2pk_addresses = script_pub_keys
+            .iter()
+            .filter(|script_pub_key| script_pub_key.kind == AddressKind::PubKey)
+            .flat_map(|script_pub_key| script_pub_key.hex.as_deref().and_then(p2pk_to_p2pkh))
             .unique_by(|address| address.clone())
             .collect::<Vec<String>>();
 
         BlockData::builder()
             .block_number(block_number)
             .addresses(addresses)
+            .p2pk_addresses(p2pk_addresses)
             .build()
     }
 }
+
+/// P2PK outputs have no address, they are indexed as the P2PKH address of their public key
+fn p2pk_to_p2pkh(script_hex: &str) -> Option<String> {
+    p2pk_script_to_p2pkh(&Script::from(Vec::<u8>::from_hex(script_hex).ok()?))
+}
+
+pub(super) fn p2pk_script_to_p2pkh(script: &Script) -> Option<String> {
+    if !script.is_p2pk() {
+        return None;
+    }
+
+    let bytes = script.as_bytes();
+    let public_key = PublicKey::from_slice(&bytes[1..bytes.len() - 1]).ok()?;
+
+    Some(Address::p2pkh(&public_key, Network::Bitcoin).to_string())
+}
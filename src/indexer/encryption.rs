@@ -0,0 +1,79 @@
+use age::x25519;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Header of ASCII armored age ciphertexts
+const ARMOR_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const IDENTITY_PREFIX: &str = "AGE-SECRET-KEY-";
+
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("Invalid age recipient {0}")]
+    InvalidRecipient(String),
+    #[error("No age X25519 identity found in the identity file")]
+    InvalidIdentity,
+    #[error("IO Error {0:?}")]
+    IoError(#[from] io::Error),
+    #[error("Encrypt Error {0:?}")]
+    EncryptError(#[from] age::EncryptError),
+    #[error("Decrypt Error {0:?}")]
+    DecryptError(#[from] age::DecryptError),
+    #[error("Decrypted secret is not valid UTF-8")]
+    InvalidUtf8,
+}
+
+/// Encrypts recovered secrets to an age X25519 recipient (`age1...`) before they are stored
+pub struct SecretEncryptor {
+    recipient: x25519::Recipient,
+}
+
+/// Decrypts stored secrets with the age X25519 identity of the recipient
+pub struct SecretDecryptor {
+    identity: x25519::Identity,
+}
+
+impl SecretEncryptor {
+    pub fn new(raw_recipient: &str) -> Result<Self, EncryptionError> {
+        let recipient = x25519::Recipient::from_str(raw_recipient.trim())
+            .map_err(|_| EncryptionError::InvalidRecipient(raw_recipient.to_string()))?;
+
+        Ok(Self { recipient })
+    }
+
+    /// Returns the ASCII armored ciphertext
+    pub fn encrypt(&self, secret: &str) -> Result<String, EncryptionError> {
+        let ciphertext = age::encrypt_and_armor(&self.recipient, secret.as_bytes())?;
+
+        Ok(ciphertext)
+    }
+}
+
+impl SecretDecryptor {
+    /// Reads the first X25519 identity of an identity file, e.g. one made by `age-keygen`
+    pub fn read(path: &Path) -> Result<Self, EncryptionError> {
+        let content = Zeroizing::new(fs::read_to_string(path)?);
+
+        let identity = content
+            .lines()
+            .map(str::trim)
+            .find(|line| line.starts_with(IDENTITY_PREFIX))
+            .and_then(|line| x25519::Identity::from_str(line).ok())
+            .ok_or(EncryptionError::InvalidIdentity)?;
+
+        Ok(Self { identity })
+    }
+
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String, EncryptionError> {
+        let plaintext = age::decrypt(&self.identity, ciphertext.as_bytes())?;
+
+        String::from_utf8(plaintext).map_err(|_| EncryptionError::InvalidUtf8)
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.trim_start().starts_with(ARMOR_HEADER)
+}
@@ -0,0 +1,126 @@
+use super::FoundAddressRecord;
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+const COLUMNS: [&str; 8] = [
+    "id",
+    "found_at",
+    "address",
+    "derivation_path",
+    "script_type",
+    "source",
+    "acknowledged_at",
+    "mnemonic",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(raw_format: &str) -> Result<Self, Self::Err> {
+        match raw_format {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "Unknown output format {raw_format}, supported formats are table, json and csv"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Renders found addresses, mnemonics are rendered as they are in the records
+pub fn render_found_addresses(records: &[FoundAddressRecord], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(records).unwrap(),
+        OutputFormat::Csv => {
+            let mut output = COLUMNS.join(",");
+
+            for record in records {
+                let row: Vec<String> = cells(record).iter().map(|cell| csv_cell(cell)).collect();
+
+                output.push('\n');
+                output.push_str(&row.join(","));
+            }
+
+            output
+        }
+        OutputFormat::Table => {
+            let rows: Vec<[String; 8]> = records.iter().map(cells).collect();
+            let widths: Vec<usize> = (0..COLUMNS.len())
+                .map(|column| {
+                    rows.iter()
+                        .map(|row| row[column].len())
+                        .chain([COLUMNS[column].len()])
+                        .max()
+                        .unwrap()
+                })
+                .collect();
+
+            let header = COLUMNS.map(String::from);
+
+            [header]
+                .iter()
+                .chain(&rows)
+                .map(|row| {
+                    row.iter()
+                        .zip(&widths)
+                        .map(|(cell, width)| format!("{cell:width$}"))
+                        .collect::<Vec<String>>()
+                        .join("  ")
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+    }
+}
+
+pub fn format_time(time: &SystemTime) -> String {
+    humantime::format_rfc3339_seconds(*time).to_string()
+}
+
+fn cells(record: &FoundAddressRecord) -> [String; 8] {
+    [
+        record.id.to_string(),
+        format_time(&record.found_at),
+        record.address.clone(),
+        record.derivation_path.clone(),
+        record.script_type.clone(),
+        record.source.clone(),
+        record
+            .acknowledged_at
+            .as_ref()
+            .map(format_time)
+            .unwrap_or_default(),
+        record.mnemonic.clone(),
+    ]
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,595 @@
+use super::repo::StagedAddress;
+use super::repo::StagedBlock;
+use super::Repo;
+use super::RepoError;
+use bitcoin::blockdata::script::Script;
+use bitcoin::hashes::sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::HashEngine;
+use bitcoin::network::constants::Network;
+use bitcoin::util::address::Address;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use thiserror::Error;
+
+const MAGIC_BYTES: [u8; 8] = *b"FGINDEX\0";
+const VERSION: u16 = 1;
+const CHECKSUM_SIZE: u64 = 32;
+/// Addresses sorted in memory at once, the sorted runs are merged into the file
+const SORT_RUN_SIZE: i64 = 1_000_000;
+/// Sort runs open at once while merging, more runs are merged in several passes
+const MAX_MERGE_RUNS: usize = 64;
+/// Number of addresses copied into the staging table at once
+const COPY_CHUNK_SIZE: usize = 100_000;
+/// Record flag of P2PKH addresses derived from P2PK outputs
+const P2PK_FLAG: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum IndexFileError {
+    #[error("IO Error {0:?}")]
+    IoError(#[from] io::Error),
+    #[error("Repo Error {0:?}")]
+    RepoError(#[from] RepoError),
+    #[error("Not an index file")]
+    NotAnIndexFile,
+    #[error("Unsupported index file version {0}")]
+    UnsupportedVersion(u16),
+    #[error("Checksum mismatch, the index file is corrupted")]
+    ChecksumMismatch,
+    #[error("Indexed address {0} can't be encoded")]
+    InvalidAddress(String),
+    #[error("Key {0:02x?} is not an address script")]
+    InvalidKey(Vec<u8>),
+}
+
+/// Metadata at the start of an index file.
+///
+/// The file continues with the addresses as their output scripts, sorted and prefix compressed,
+/// and ends with the SHA-256 of everything before it. Blocks are only stored as ranges.
+#[derive(Debug, Clone)]
+pub struct IndexFileHeader {
+    pub version: u16,
+    pub block_ranges: Vec<RangeInclusive<u64>>,
+    pub address_count: u64,
+}
+
+impl IndexFileHeader {
+    pub fn block_count(&self) -> u64 {
+        self.block_ranges
+            .iter()
+            .map(|range| range.end() - range.start() + 1)
+            .sum()
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        writer.write_all(&MAGIC_BYTES)?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&(self.block_ranges.len() as u32).to_le_bytes())?;
+
+        for range in &self.block_ranges {
+            writer.write_all(&(*range.start() as u32).to_le_bytes())?;
+            writer.write_all(&(*range.end() as u32).to_le_bytes())?;
+        }
+
+        writer.write_all(&self.address_count.to_le_bytes())
+    }
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self, IndexFileError> {
+        let magic_bytes: [u8; 8] =
+            read_array(reader).map_err(|_| IndexFileError::NotAnIndexFile)?;
+
+        if magic_bytes != MAGIC_BYTES {
+            return Err(IndexFileError::NotAnIndexFile);
+        }
+
+        let version = u16::from_le_bytes(read_array(reader)?);
+
+        if version != VERSION {
+            return Err(IndexFileError::UnsupportedVersion(version));
+        }
+
+        let range_count = u32::from_le_bytes(read_array(reader)?);
+        let mut block_ranges = vec![];
+
+        for _ in 0..range_count {
+            let start = u32::from_le_bytes(read_array(reader)?);
+            let end = u32::from_le_bytes(read_array(reader)?);
+
+            block_ranges.push(u64::from(start)..=u64::from(end));
+        }
+
+        let address_count = u64::from_le_bytes(read_array(reader)?);
+
+        Ok(Self {
+            version,
+            block_ranges,
+            address_count,
+        })
+    }
+}
+
+impl fmt::Display for IndexFileHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Index file version {}", self.version)?;
+        writeln!(
+            f,
+            "Blocks: {} in {} ranges",
+            self.block_count(),
+            self.block_ranges.len()
+        )?;
+
+        for range in &self.block_ranges {
+            writeln!(f, "  {}..={}", range.start(), range.end())?;
+        }
+
+        write!(f, "Addresses: {}", self.address_count)
+    }
+}
+
+/// Writes the addresses and indexed block ranges to a file.
+///
+/// Addresses are sorted in runs next to the file and merged, so the index doesn't have to fit into
+/// memory. Blocks indexed while exporting may be missing from the file.
+pub fn export_index(repo: &Repo, path: &Path) -> Result<IndexFileHeader, IndexFileError> {
+    let block_ranges = repo.indexed_block_ranges()?;
+    let mut runs = SortRuns::new(path);
+    let mut last_address = None;
+
+    loop {
+        let addresses = repo.addresses_after(last_address.as_deref(), SORT_RUN_SIZE)?;
+
+        last_address = match addresses.last() {
+            Some((address, _)) => Some(address.clone()),
+            None => break,
+        };
+
+        let entries = addresses
+            .iter()
+            .map(|(address, script_type)| {
+                let flags = match script_type.as_deref() {
+                    Some("p2pk") => P2PK_FLAG,
+                    _ => 0,
+                };
+
+                Ok((address_key(address)?, flags))
+            })
+            .collect::<Result<Vec<(Vec<u8>, u8)>, IndexFileError>>()?;
+
+        runs.add(entries)?;
+
+        log::info!("Sorted {} addresses", runs.entry_count);
+    }
+
+    runs.write_index_file(block_ranges)
+}
+
+/// Checks the checksum of an index file and returns its header
+pub fn verify_index_file(path: &Path) -> Result<IndexFileHeader, IndexFileError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = IndexFileHeader::read(&mut reader)?;
+
+    let length = fs::metadata(path)?.len();
+
+    if length < CHECKSUM_SIZE {
+        return Err(IndexFileError::NotAnIndexFile);
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut engine = sha256::Hash::engine();
+
+    io::copy(&mut (&mut reader).take(length - CHECKSUM_SIZE), &mut engine)?;
+
+    let checksum: [u8; 32] = read_array(&mut reader)?;
+
+    if sha256::Hash::from_engine(engine).into_inner() != checksum {
+        return Err(IndexFileError::ChecksumMismatch);
+    }
+
+    Ok(header)
+}
+
+/// Loads a verified index file like a bulk sync, returns its header and the number of new
+/// addresses.
+///
+/// Addresses and blocks are merged into the existing index, rows left in the staging tables by
+/// `index --bulk` are merged with them.
+pub fn import_index(repo: &Repo, path: &Path) -> Result<(IndexFileHeader, usize), IndexFileError> {
+    let header = verify_index_file(path)?;
+
+    let mut chunk = Vec::with_capacity(COPY_CHUNK_SIZE);
+    let mut read_addresses: u64 = 0;
+
+    read_index_addresses(path, |address| {
+        chunk.push(address);
+        read_addresses += 1;
+
+        if chunk.len() == COPY_CHUNK_SIZE {
+            repo.copy_staged_addresses(&chunk)?;
+            chunk.clear();
+
+            log::info!(
+                "Staged {read_addresses} of {} addresses",
+                header.address_count
+            );
+        }
+
+        Ok(())
+    })?;
+
+    repo.copy_staged_addresses(&chunk)?;
+
+    // blocks are staged last, a failed import never marks blocks indexed without their addresses
+    let blocks: Vec<StagedBlock> = header
+        .block_ranges
+        .iter()
+        .flat_map(|range| range.clone())
+        .map(|block_number| StagedBlock {
+            block_number: block_number as i32,
+        })
+        .collect();
+
+    repo.copy_staged_blocks(&blocks)?;
+
+    log::info!("Merging {read_addresses} addresses into the index");
+
+    let new_addresses = repo.merge_staged_block_data()?;
+
+    Ok((header, new_addresses))
+}
+
+/// Passes the addresses of an index file to `stage` in file order, the checksum isn't verified
+fn read_index_addresses<F>(path: &Path, mut stage: F) -> Result<(), IndexFileError>
+where
+    F: FnMut(StagedAddress) -> Result<(), IndexFileError>,
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = IndexFileHeader::read(&mut reader)?;
+
+    let mut key = vec![];
+
+    for _ in 0..header.address_count {
+        let [flags, shared_length, suffix_length] = read_array(&mut reader)?;
+
+        key.truncate(usize::from(shared_length));
+        key.extend(read_bytes(&mut reader, usize::from(suffix_length))?);
+
+        let address = Address::from_script(&Script::from(key.clone()), Network::Bitcoin)
+            .map_err(|_| IndexFileError::InvalidKey(key.clone()))?;
+
+        stage(StagedAddress {
+            address: address.to_string(),
+            script_type: (flags & P2PK_FLAG != 0).then(|| "p2pk".to_string()),
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Output script of an address, keys of P2PKH addresses derived from P2PK outputs are flagged
+fn address_key(address: &str) -> Result<Vec<u8>, IndexFileError> {
+    let address = Address::from_str(address)
+        .map_err(|_| IndexFileError::InvalidAddress(address.to_string()))?;
+
+    Ok(address.script_pubkey().into_bytes())
+}
+
+/// Temporary files of sorted address runs next to the index file, removed when dropped
+struct SortRuns {
+    path: PathBuf,
+    paths: Vec<PathBuf>,
+    /// Number of run files created so far, names are never reused
+    created: usize,
+    entry_count: u64,
+}
+
+impl SortRuns {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            paths: vec![],
+            created: 0,
+            entry_count: 0,
+        }
+    }
+
+    /// Sorts the entries and writes them to a new run
+    fn add(&mut self, mut entries: Vec<(Vec<u8>, u8)>) -> Result<(), io::Error> {
+        entries.sort_unstable();
+
+        let mut writer = BufWriter::new(File::create(self.next_path())?);
+
+        for (key, flags) in &entries {
+            write_run_entry(&mut writer, key, *flags)?;
+        }
+
+        writer.flush()?;
+        self.entry_count += entries.len() as u64;
+
+        Ok(())
+    }
+
+    /// Merges the runs into the index file with the checksum at the end
+    fn write_index_file(
+        mut self,
+        block_ranges: Vec<RangeInclusive<u64>>,
+    ) -> Result<IndexFileHeader, IndexFileError> {
+        self.reduce()?;
+
+        let header = IndexFileHeader {
+            version: VERSION,
+            block_ranges,
+            address_count: self.entry_count,
+        };
+
+        let mut writer = HashingWriter {
+            inner: BufWriter::new(File::create(&self.path)?),
+            engine: sha256::Hash::engine(),
+        };
+
+        header.write(&mut writer)?;
+
+        // every key is stored as the length of the prefix it shares with the previous one and the
+        // rest of it
+        let mut previous_key = vec![];
+
+        merge_runs(&self.paths, |key, flags| {
+            let shared_length = previous_key
+                .iter()
+                .zip(&key)
+                .take_while(|(previous, next)| previous == next)
+                .count();
+
+            writer.write_all(&[
+                flags,
+                shared_length as u8,
+                (key.len() - shared_length) as u8,
+            ])?;
+            writer.write_all(&key[shared_length..])?;
+
+            previous_key = key;
+
+            Ok(())
+        })?;
+
+        let checksum = sha256::Hash::from_engine(writer.engine);
+        writer.inner.write_all(&checksum.into_inner())?;
+        writer.inner.flush()?;
+
+        Ok(header)
+    }
+
+    /// Merges runs in passes of at most `MAX_MERGE_RUNS`, until the rest can be merged at once
+    fn reduce(&mut self) -> Result<(), io::Error> {
+        while self.paths.len() > MAX_MERGE_RUNS {
+            let pass = self.paths[..MAX_MERGE_RUNS].to_vec();
+            let mut writer = BufWriter::new(File::create(self.next_path())?);
+
+            merge_runs(&pass, |key, flags| {
+                write_run_entry(&mut writer, &key, flags)
+            })?;
+            writer.flush()?;
+
+            for path in &pass {
+                fs::remove_file(path)?;
+            }
+
+            self.paths.drain(..MAX_MERGE_RUNS);
+        }
+
+        Ok(())
+    }
+
+    /// Path of a new run, tracked right away so it's removed even if writing it fails
+    fn next_path(&mut self) -> PathBuf {
+        let path = self.path.with_extension(format!("run{}", self.created));
+        self.created += 1;
+        self.paths.push(path.clone());
+
+        path
+    }
+}
+
+impl Drop for SortRuns {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            if let Err(error) = fs::remove_file(path) {
+                log::warn!("Failed to remove the sort run {path:?} - {error:?}");
+            }
+        }
+    }
+}
+
+/// Passes the entries of the sorted runs to `write` in order
+fn merge_runs<F>(paths: &[PathBuf], mut write: F) -> Result<(), io::Error>
+where
+    F: FnMut(Vec<u8>, u8) -> Result<(), io::Error>,
+{
+    let mut readers = paths
+        .iter()
+        .map(|path| File::open(path).map(BufReader::new))
+        .collect::<Result<Vec<BufReader<File>>, io::Error>>()?;
+
+    let mut heap = BinaryHeap::new();
+
+    for (index, reader) in readers.iter_mut().enumerate() {
+        if let Some((key, flags)) = read_run_entry(reader)? {
+            heap.push(Reverse((key, flags, index)));
+        }
+    }
+
+    while let Some(Reverse((key, flags, index))) = heap.pop() {
+        if let Some((next_key, next_flags)) = read_run_entry(&mut readers[index])? {
+            heap.push(Reverse((next_key, next_flags, index)));
+        }
+
+        write(key, flags)?;
+    }
+
+    Ok(())
+}
+
+fn write_run_entry<W: Write>(writer: &mut W, key: &[u8], flags: u8) -> Result<(), io::Error> {
+    writer.write_all(&[flags, key.len() as u8])?;
+    writer.write_all(key)
+}
+
+fn read_run_entry<R: Read>(reader: &mut R) -> Result<Option<(Vec<u8>, u8)>, io::Error> {
+    let mut flags = [0];
+
+    if reader.read(&mut flags)? == 0 {
+        return Ok(None);
+    }
+
+    let [key_length] = read_array(reader)?;
+    let key = read_bytes(reader, usize::from(key_length))?;
+
+    Ok(Some((key, flags[0])))
+}
+
+/// Passes writes through and hashes them for the checksum
+struct HashingWriter<W: Write> {
+    inner: W,
+    engine: sha256::HashEngine,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(bytes)?;
+        self.engine.input(&bytes[..written]);
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], io::Error> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn read_bytes<R: Read>(reader: &mut R, length: usize) -> Result<Vec<u8>, io::Error> {
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::WScriptHash;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("foogold-{name}-{}.fgindex", std::process::id()))
+    }
+
+    /// Distinct P2WSH addresses, every third one flagged as derived from a P2PK output
+    fn addresses(count: u8) -> Vec<StagedAddress> {
+        (0..count)
+            .map(|seed| {
+                let script = Script::new_v0_p2wsh(&WScriptHash::hash(&[seed]));
+
+                StagedAddress {
+                    address: Address::from_script(&script, Network::Bitcoin)
+                        .unwrap()
+                        .to_string(),
+                    script_type: (seed % 3 == 0).then(|| "p2pk".to_string()),
+                }
+            })
+            .collect()
+    }
+
+    fn write_file(path: &Path, addresses: &[StagedAddress]) -> IndexFileHeader {
+        let mut runs = SortRuns::new(path);
+
+        // one run per address pair, more runs than a single merge pass takes
+        for pair in addresses.chunks(2) {
+            let entries = pair
+                .iter()
+                .map(|address| {
+                    let flags = match address.script_type.as_deref() {
+                        Some("p2pk") => P2PK_FLAG,
+                        _ => 0,
+                    };
+
+                    (address_key(&address.address).unwrap(), flags)
+                })
+                .collect();
+
+            runs.add(entries).unwrap();
+        }
+
+        assert!(runs.paths.len() > MAX_MERGE_RUNS);
+
+        runs.write_index_file(vec![0..=9, 20..=29]).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        let mut addresses = addresses(200);
+
+        let header = write_file(&path, &addresses);
+        assert_eq!(header.address_count, 200);
+
+        let verified = verify_index_file(&path).unwrap();
+        assert_eq!(verified.address_count, 200);
+        assert_eq!(verified.block_ranges, vec![0..=9, 20..=29]);
+        assert_eq!(verified.block_count(), 20);
+
+        let mut read = vec![];
+        read_index_addresses(&path, |address| {
+            read.push((address.address, address.script_type));
+            Ok(())
+        })
+        .unwrap();
+
+        addresses.sort_by_key(|address| address_key(&address.address).unwrap());
+        let expected: Vec<(String, Option<String>)> = addresses
+            .into_iter()
+            .map(|address| (address.address, address.script_type))
+            .collect();
+
+        assert_eq!(read, expected);
+
+        // 100 runs of address pairs and one run merged from the first 64 of them
+        for run in 0..101 {
+            assert!(!path.with_extension(format!("run{run}")).exists());
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_corrupted_byte() {
+        let path = temp_path("corrupted");
+        write_file(&path, &addresses(200));
+
+        let mut bytes = fs::read(&path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+
+        let result = verify_index_file(&path);
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(IndexFileError::ChecksumMismatch)));
+    }
+}
@@ -0,0 +1,157 @@
+use super::Repo;
+use super::RepoError;
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+use std::ops::RangeInclusive;
+
+/// Coverage of the chain index
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStatus {
+    pub indexed_ranges: Vec<RangeInclusive<u64>>,
+    /// Gaps between block 0 and the chain tip, or the highest indexed block without a node
+    pub missing_ranges: Vec<RangeInclusive<u64>>,
+    pub estimated_address_count: u64,
+    pub failed_block_count: usize,
+    /// Blocks copied by bulk indexing that are not merged into the index yet
+    pub staged_block_count: i64,
+    pub tip_height: Option<u64>,
+}
+
+impl IndexStatus {
+    pub fn read(repo: &Repo, tip_height: Option<u64>) -> Result<Self, RepoError> {
+        let indexed_ranges = repo.indexed_block_ranges()?;
+
+        let end = match (tip_height, indexed_ranges.last()) {
+            (Some(tip_height), _) => tip_height + 1,
+            (None, Some(last_range)) => last_range.end() + 1,
+            (None, None) => 0,
+        };
+
+        Ok(Self {
+            missing_ranges: missing_ranges(&indexed_ranges, 0..end),
+            indexed_ranges,
+            estimated_address_count: repo.estimated_address_count()?,
+            failed_block_count: repo.failed_blocks()?.len(),
+            staged_block_count: repo.staged_block_count()?,
+            tip_height,
+        })
+    }
+
+    pub fn indexed_block_count(&self) -> u64 {
+        self.indexed_ranges.iter().map(range_size).sum()
+    }
+
+    pub fn missing_block_count(&self) -> u64 {
+        self.missing_ranges.iter().map(range_size).sum()
+    }
+
+    pub fn highest_indexed_block(&self) -> Option<u64> {
+        self.indexed_ranges.last().map(|range| *range.end())
+    }
+}
+
+impl fmt::Display for IndexStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Indexed blocks: {} in {} ranges",
+            self.indexed_block_count(),
+            self.indexed_ranges.len()
+        )?;
+
+        for range in &self.indexed_ranges {
+            writeln!(f, "  {}..={}", range.start(), range.end())?;
+        }
+
+        writeln!(f, "Missing blocks: {}", self.missing_block_count())?;
+
+        for range in &self.missing_ranges {
+            writeln!(f, "  {}..={}", range.start(), range.end())?;
+        }
+
+        if let Some(tip_height) = self.tip_height {
+            let behind = match self.highest_indexed_block() {
+                Some(highest_indexed_block) => tip_height.saturating_sub(highest_indexed_block),
+                None => tip_height + 1,
+            };
+
+            writeln!(f, "Node tip: {tip_height}, {behind} blocks behind")?;
+        }
+
+        writeln!(f, "Failed blocks: {}", self.failed_block_count)?;
+
+        if self.staged_block_count > 0 {
+            writeln!(
+                f,
+                "Staged blocks: {}, merge them with `index finish-bulk`",
+                self.staged_block_count
+            )?;
+        }
+
+        write!(f, "Addresses: ~{} (estimate)", self.estimated_address_count)
+    }
+}
+
+/// Heights of `blocks` not covered by the sorted `indexed_ranges`
+pub fn missing_ranges(
+    indexed_ranges: &[RangeInclusive<u64>],
+    blocks: Range<u64>,
+) -> Vec<RangeInclusive<u64>> {
+    let mut missing_ranges = vec![];
+    let mut next = blocks.start;
+
+    for range in indexed_ranges {
+        if next >= blocks.end {
+            break;
+        }
+
+        if *range.start() > next {
+            missing_ranges.push(next..=(*range.start()).min(blocks.end) - 1);
+        }
+
+        next = next.max(range.end() + 1);
+    }
+
+    if next < blocks.end {
+        missing_ranges.push(next..=blocks.end - 1);
+    }
+
+    missing_ranges
+}
+
+fn range_size(range: &RangeInclusive<u64>) -> u64 {
+    range.end() - range.start() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_from_empty_index() {
+        assert_eq!(missing_ranges(&[], 0..5), vec![0..=4]);
+        assert_eq!(missing_ranges(&[], 0..0), vec![]);
+    }
+
+    #[test]
+    fn missing_gap_at_genesis() {
+        assert_eq!(missing_ranges(&[3..=5], 0..8), vec![0..=2, 6..=7]);
+        assert_eq!(missing_ranges(&[0..=5], 0..8), vec![6..=7]);
+    }
+
+    #[test]
+    fn adjacent_and_overlapping_ranges_leave_no_gap() {
+        assert_eq!(missing_ranges(&[0..=4, 5..=9], 0..12), vec![10..=11]);
+        assert_eq!(missing_ranges(&[0..=5, 3..=8], 0..10), vec![9..=9]);
+        assert_eq!(missing_ranges(&[0..=9, 2..=4], 0..12), vec![10..=11]);
+    }
+
+    #[test]
+    fn end_inside_last_range() {
+        assert_eq!(missing_ranges(&[0..=4, 10..=20], 0..15), vec![5..=9]);
+        assert_eq!(missing_ranges(&[0..=4, 10..=20], 0..10), vec![5..=9]);
+        assert_eq!(missing_ranges(&[10..=12], 0..5), vec![0..=4]);
+        assert_eq!(missing_ranges(&[0..=20], 0..15), vec![]);
+    }
+}
@@ -0,0 +1,230 @@
+use super::ClientError;
+use super::IndexStatus;
+use super::Repo;
+use super::RepoError;
+use super::RpcClient;
+use serde::Serialize;
+use std::error::Error;
+use std::io::Read;
+use std::net::ToSocketAddrs;
+use std::thread;
+use thiserror::Error;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+use typed_builder::TypedBuilder;
+
+/// Largest accepted body of a batch lookup
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+const MAX_BATCH_SIZE: usize = 10_000;
+
+#[derive(Error, Debug)]
+pub enum LookupServerError {
+    #[error("Failed to listen on {address} - {error}")]
+    Bind {
+        address: String,
+        error: Box<dyn Error + Send + Sync>,
+    },
+    #[error("{0} is not a loopback address, the API has no authentication")]
+    RemoteAddress(String),
+}
+
+/// Read-only HTTP API over the index, found mnemonics are only served as fingerprints
+#[derive(TypedBuilder)]
+pub struct LookupServer {
+    repo: Repo,
+    /// Node the chain tip of the index status is read from
+    #[builder(default)]
+    client: Option<RpcClient>,
+    #[builder(setter(into))]
+    listen_address: String,
+    /// Listens on addresses other than loopback ones, anyone who can reach them can query the index
+    #[builder(default)]
+    allow_remote: bool,
+    /// Requests handled at the same time, each of them holds a database connection
+    #[builder(default = 4)]
+    threads: usize,
+}
+
+#[derive(Serialize)]
+struct AddressLookup {
+    address: String,
+    exists: bool,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Failed request with the HTTP status it's answered with
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<RepoError> for ApiError {
+    fn from(error: RepoError) -> Self {
+        log::error!("Failed to query the index - {error:?}");
+
+        ApiError::new(500, "Database error")
+    }
+}
+
+impl From<ClientError> for ApiError {
+    fn from(error: ClientError) -> Self {
+        log::error!("Failed to get the chain tip - {error:?}");
+
+        ApiError::new(502, "Node error")
+    }
+}
+
+impl LookupServer {
+    /// Serves requests until the process is stopped
+    pub fn run(&self) -> Result<(), LookupServerError> {
+        if !self.allow_remote && !self.is_loopback() {
+            return Err(LookupServerError::RemoteAddress(
+                self.listen_address.clone(),
+            ));
+        }
+
+        let server =
+            Server::http(&self.listen_address).map_err(|error| LookupServerError::Bind {
+                address: self.listen_address.clone(),
+                error,
+            })?;
+
+        log::info!("Serving index lookups on http://{}", self.listen_address);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| {
+                    for request in server.incoming_requests() {
+                        self.handle(request);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Whether every address the listen address resolves to is a loopback one
+    fn is_loopback(&self) -> bool {
+        match self.listen_address.to_socket_addrs() {
+            Ok(mut addresses) => addresses.all(|address| address.ip().is_loopback()),
+            // binding fails with a proper error
+            Err(_) => true,
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let (status, body) = match self.route(&mut request) {
+            Ok(body) => (200, body),
+            Err(error) => (
+                error.status,
+                serde_json::to_string(&ErrorBody {
+                    error: error.message,
+                })
+                .unwrap(),
+            ),
+        };
+
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+
+        if let Err(error) = request.respond(response) {
+            log::warn!("Failed to answer a lookup request - {error:?}");
+        }
+    }
+
+    fn route(&self, request: &mut Request) -> Result<String, ApiError> {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+        match (request.method(), path) {
+            (Method::Get, "/index/status") => self.index_status(),
+            (Method::Get, "/found") => self.found_addresses(query),
+            (Method::Post, "/addresses/exists") => self.addresses_exist(request),
+            (Method::Get, _) => match path.strip_prefix("/addresses/") {
+                Some(address) if !address.is_empty() => self.address_exists(address),
+                _ => Err(ApiError::new(404, "Not found")),
+            },
+            _ => Err(ApiError::new(404, "Not found")),
+        }
+    }
+
+    fn address_exists(&self, address: &str) -> Result<String, ApiError> {
+        let lookup = AddressLookup {
+            address: address.to_string(),
+            exists: self.repo.address_exists(address)?,
+        };
+
+        Ok(serde_json::to_string(&lookup).unwrap())
+    }
+
+    /// Looks up a JSON array of addresses, results are in the order of the request
+    fn addresses_exist(&self, request: &mut Request) -> Result<String, ApiError> {
+        let addresses: Vec<String> =
+            serde_json::from_reader(request.as_reader().take(MAX_BODY_SIZE))
+                .map_err(|_| ApiError::new(400, "Expected a JSON array of addresses"))?;
+
+        if addresses.len() > MAX_BATCH_SIZE {
+            return Err(ApiError::new(
+                400,
+                format!("At most {MAX_BATCH_SIZE} addresses can be looked up at once"),
+            ));
+        }
+
+        let existing_addresses = self.repo.existing_addresses(&addresses)?;
+
+        let lookups: Vec<AddressLookup> = addresses
+            .into_iter()
+            .map(|address| AddressLookup {
+                exists: existing_addresses.contains(&address),
+                address,
+            })
+            .collect();
+
+        Ok(serde_json::to_string(&lookups).unwrap())
+    }
+
+    fn index_status(&self) -> Result<String, ApiError> {
+        let tip_height = match &self.client {
+            Some(client) => Some(client.get_block_count()?),
+            None => None,
+        };
+
+        let status = IndexStatus::read(&self.repo, tip_height)?;
+
+        Ok(serde_json::to_string(&status).unwrap())
+    }
+
+    /// Found addresses with redacted mnemonics, `?unacknowledged=true` skips acknowledged ones
+    fn found_addresses(&self, query: &str) -> Result<String, ApiError> {
+        let unacknowledged_only = query
+            .split('&')
+            .any(|pair| pair == "unacknowledged=true" || pair == "unacknowledged");
+
+        let mut found_addresses = self.repo.found_addresses(unacknowledged_only)?;
+
+        for found_address in &mut found_addresses {
+            found_address.redact_mnemonic();
+        }
+
+        Ok(serde_json::to_string(&found_addresses).unwrap())
+    }
+}
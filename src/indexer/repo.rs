@@ -1,14 +1,54 @@
+use super::format_time;
+use super::is_encrypted;
 use super::BlockData;
+use super::EncryptionError;
+use super::SecretEncryptor;
+use crate::checker::Redacted;
 use crate::schema::addresses;
 use crate::schema::blocks;
+use crate::schema::failed_blocks;
 use crate::schema::found_addresses;
+use crate::schema::shard_progress;
+use crate::schema::utxo_addresses;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2 as diesel_r2d2;
+use diesel::sql_types::BigInt;
+use diesel::sql_types::Integer;
 use diesel::Connection;
 use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde::Serializer;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::SystemTime;
 use thiserror::Error;
 use typed_builder::TypedBuilder;
+use zeroize::Zeroize;
+use zeroize::ZeroizeOnDrop;
+
+// staging tables have no primary key, so they are left out of the generated schema
+diesel::table! {
+    address_staging (address) {
+        address -> Text,
+        script_type -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    block_staging (block_number) {
+        block_number -> Int4,
+    }
+}
+
+diesel::table! {
+    utxo_address_staging (address) {
+        address -> Text,
+        script_type -> Nullable<Text>,
+    }
+}
 
 static POOL: OnceCell<diesel_r2d2::Pool<diesel_r2d2::ConnectionManager<PgConnection>>> =
     OnceCell::new();
@@ -17,6 +57,27 @@ static POOL: OnceCell<diesel_r2d2::Pool<diesel_r2d2::ConnectionManager<PgConnect
 #[diesel(table_name = addresses)]
 pub struct Address {
     address: String,
+    script_type: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = address_staging, treat_none_as_default_value = false)]
+pub struct StagedAddress {
+    pub address: String,
+    pub script_type: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = block_staging, treat_none_as_default_value = false)]
+pub struct StagedBlock {
+    pub block_number: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = utxo_address_staging, treat_none_as_default_value = false)]
+pub struct StagedUtxoAddress {
+    pub address: String,
+    pub script_type: Option<String>,
 }
 
 #[derive(Error, Debug)]
@@ -25,20 +86,148 @@ pub enum RepoError {
     PoolError(#[from] r2d2::Error),
     #[error("Diesel error {0:?}")]
     DieselError(#[from] diesel::result::Error),
+    #[error("Encryption error {0:?}")]
+    Encryption(#[from] EncryptionError),
 }
 
 #[derive(TypedBuilder)]
 pub struct Repo {
     database_url: String,
     pool_size: u32,
+    /// Found mnemonics are stored encrypted if present
+    #[builder(default)]
+    encryptor: Option<SecretEncryptor>,
+    /// Run name stored with found addresses that don't have one
+    #[builder(default, setter(into))]
+    source: String,
+    /// Addresses `address_exists` looks up
+    #[builder(default)]
+    address_set: AddressSet,
+}
+
+/// Set of indexed addresses checked against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressSet {
+    /// Addresses that appeared in any output of the indexed blocks
+    #[default]
+    Used,
+    /// Addresses holding outputs in the imported UTXO snapshot
+    Unspent,
 }
 
-#[derive(Insertable, Clone, Debug, TypedBuilder)]
-#[diesel(table_name = found_addresses)]
+impl FromStr for AddressSet {
+    type Err = String;
+
+    fn from_str(raw_set: &str) -> Result<Self, Self::Err> {
+        match raw_set {
+            "used" => Ok(AddressSet::Used),
+            "unspent" => Ok(AddressSet::Unspent),
+            _ => Err(format!(
+                "Unknown address set {raw_set}, supported sets are used and unspent"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AddressSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AddressSet::Used => "used",
+            AddressSet::Unspent => "unspent",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Found address to store, the mnemonic is zeroized on drop
+#[derive(Serialize, Clone, TypedBuilder, Zeroize, ZeroizeOnDrop)]
 pub struct FoundAddress {
     pub address: String,
     pub derivation_path: String,
     pub mnemonic: String,
+    pub script_type: String,
+    #[builder(default, setter(into))]
+    pub source: String,
+}
+
+impl fmt::Debug for FoundAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FoundAddress")
+            .field("address", &self.address)
+            .field("derivation_path", &self.derivation_path)
+            .field("mnemonic", &Redacted(&self.mnemonic))
+            .field("script_type", &self.script_type)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// Stored found address, the mnemonic may be encrypted and is zeroized on drop
+#[derive(Queryable, Serialize, Clone, Zeroize, ZeroizeOnDrop)]
+pub struct FoundAddressRecord {
+    #[zeroize(skip)]
+    pub id: i64,
+    pub address: String,
+    pub derivation_path: String,
+    pub mnemonic: String,
+    pub script_type: String,
+    #[serde(serialize_with = "serialize_time")]
+    #[zeroize(skip)]
+    pub found_at: SystemTime,
+    pub source: String,
+    #[serde(serialize_with = "serialize_optional_time")]
+    #[zeroize(skip)]
+    pub acknowledged_at: Option<SystemTime>,
+}
+
+impl FoundAddressRecord {
+    /// Replaces the mnemonic with its fingerprint, or with `encrypted` if it's stored encrypted.
+    ///
+    /// Unlike logs this never shows the mnemonic, also with `--log-secrets`.
+    pub fn redact_mnemonic(&mut self) {
+        self.mnemonic = if is_encrypted(&self.mnemonic) {
+            "encrypted".to_string()
+        } else {
+            Redacted(&self.mnemonic).fingerprint()
+        };
+    }
+}
+
+/// Block that couldn't be indexed, retried with `index --retry-failed`
+#[derive(Queryable, Clone, Debug)]
+pub struct FailedBlock {
+    pub block_number: i32,
+    pub error: String,
+    pub attempts: i32,
+    pub failed_at: SystemTime,
+}
+
+#[derive(QueryableByName)]
+struct BlockRange {
+    #[diesel(sql_type = Integer)]
+    first: i32,
+    #[diesel(sql_type = Integer)]
+    last: i32,
+}
+
+#[derive(QueryableByName)]
+struct RowCount {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, TypedBuilder)]
+#[diesel(table_name = shard_progress)]
+pub struct ShardProgress {
+    #[builder(setter(into))]
+    pub search: String,
+    pub shard_index: i32,
+    pub shard_count: i32,
+    pub checked: i64,
+    pub total: i64,
+    #[builder(default_code = "SystemTime::now()")]
+    pub updated_at: SystemTime,
 }
 
 impl Repo {
@@ -54,7 +243,20 @@ impl Repo {
                 .addresses
                 .clone()
                 .into_iter()
-                .map(|address| Address { address })
+                .map(|address| Address {
+                    address,
+                    script_type: None,
+                })
+                .chain(
+                    block_data
+                        .p2pk_addresses
+                        .clone()
+                        .into_iter()
+                        .map(|address| Address {
+                            address,
+                            script_type: Some("p2pk".to_string()),
+                        }),
+                )
                 .collect();
 
             diesel::insert_into(addresses::table)
@@ -67,11 +269,233 @@ impl Repo {
         })
     }
 
-    pub fn insert_found_address(&self, found_address: FoundAddress) -> Result<usize, RepoError> {
+    /// Appends blocks to the unindexed staging tables with COPY, they are only visible in the
+    /// index after [`Repo::merge_staged_block_data`]
+    pub fn copy_block_data(&self, block_datas: &[BlockData]) -> Result<(), RepoError> {
         let mut connection = self.pool().get()?;
 
+        let staged_blocks: Vec<StagedBlock> = block_datas
+            .iter()
+            .map(|block_data| StagedBlock {
+                block_number: block_data.block_number as i32,
+            })
+            .collect();
+
+        let staged_addresses: Vec<StagedAddress> = block_datas
+            .iter()
+            .flat_map(|block_data| {
+                block_data
+                    .addresses
+                    .iter()
+                    .map(|address| StagedAddress {
+                        address: address.clone(),
+                        script_type: None,
+                    })
+                    .chain(
+                        block_data
+                            .p2pk_addresses
+                            .iter()
+                            .map(|address| StagedAddress {
+                                address: address.clone(),
+                                script_type: Some("p2pk".to_string()),
+                            }),
+                    )
+            })
+            .collect();
+
+        connection.transaction::<(), RepoError, _>(|db_connection| {
+            diesel::copy_from(address_staging::table)
+                .from_insertable(&staged_addresses)
+                .execute(db_connection)?;
+
+            diesel::copy_from(block_staging::table)
+                .from_insertable(&staged_blocks)
+                .execute(db_connection)?;
+
+            Ok(())
+        })
+    }
+
+    pub fn copy_staged_addresses(&self, addresses: &[StagedAddress]) -> Result<(), RepoError> {
+        let mut connection = self.pool().get()?;
+
+        diesel::copy_from(address_staging::table)
+            .from_insertable(addresses)
+            .execute(&mut connection)?;
+
+        Ok(())
+    }
+
+    pub fn copy_staged_blocks(&self, blocks: &[StagedBlock]) -> Result<(), RepoError> {
+        let mut connection = self.pool().get()?;
+
+        diesel::copy_from(block_staging::table)
+            .from_insertable(blocks)
+            .execute(&mut connection)?;
+
+        Ok(())
+    }
+
+    /// Moves staged blocks and deduplicated addresses into the index, returns the number of new
+    /// addresses.
+    ///
+    /// If the index is empty, its primary key is dropped during the load and built once at the end.
+    pub fn merge_staged_block_data(&self) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        connection.transaction::<usize, RepoError, _>(|db_connection| {
+            let initial_load = addresses::table
+                .select(addresses::address)
+                .first::<String>(db_connection)
+                .optional()?
+                .is_none();
+
+            // addresses found directly in outputs win over the ones derived from p2pk outputs
+            let select_staged_addresses = "SELECT DISTINCT ON (address) address, script_type \
+                 FROM address_staging \
+                 ORDER BY address, script_type NULLS FIRST";
+
+            let new_addresses = if initial_load {
+                diesel::sql_query("ALTER TABLE addresses DROP CONSTRAINT addresses_pkey")
+                    .execute(db_connection)?;
+
+                let new_addresses = diesel::sql_query(format!(
+                    "INSERT INTO addresses (address, script_type) {select_staged_addresses}"
+                ))
+                .execute(db_connection)?;
+
+                diesel::sql_query("ALTER TABLE addresses ADD PRIMARY KEY (address)")
+                    .execute(db_connection)?;
+
+                new_addresses
+            } else {
+                diesel::sql_query(format!(
+                    "INSERT INTO addresses (address, script_type) {select_staged_addresses} \
+                     ON CONFLICT (address) DO NOTHING"
+                ))
+                .execute(db_connection)?
+            };
+
+            diesel::sql_query(
+                "INSERT INTO blocks (block_number) \
+                 SELECT DISTINCT block_number FROM block_staging \
+                 ON CONFLICT (block_number) DO NOTHING",
+            )
+            .execute(db_connection)?;
+
+            diesel::sql_query("TRUNCATE address_staging, block_staging").execute(db_connection)?;
+
+            Ok(new_addresses)
+        })
+    }
+
+    pub fn staged_block_count(&self) -> Result<i64, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let row_count =
+            diesel::sql_query("SELECT COUNT(DISTINCT block_number) AS count FROM block_staging")
+                .get_result::<RowCount>(&mut connection)?;
+
+        Ok(row_count.count)
+    }
+
+    /// Empties the UTXO staging table before a snapshot import
+    pub fn clear_staged_utxo_addresses(&self) -> Result<(), RepoError> {
+        let mut connection = self.pool().get()?;
+
+        diesel::sql_query("TRUNCATE utxo_address_staging").execute(&mut connection)?;
+
+        Ok(())
+    }
+
+    pub fn copy_utxo_addresses(&self, addresses: &[StagedUtxoAddress]) -> Result<(), RepoError> {
+        let mut connection = self.pool().get()?;
+
+        diesel::copy_from(utxo_address_staging::table)
+            .from_insertable(addresses)
+            .execute(&mut connection)?;
+
+        Ok(())
+    }
+
+    /// Replaces the UTXO addresses with the deduplicated staged ones, returns their number.
+    ///
+    /// The addresses are loaded into a new table that is renamed in place of the old one, so
+    /// readers keep using the previous set while it loads. They only wait for the swap at the end.
+    pub fn replace_utxo_addresses(&self) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        connection.transaction::<usize, RepoError, _>(|db_connection| {
+            diesel::sql_query(
+                "CREATE TABLE utxo_addresses_new (LIKE utxo_addresses INCLUDING DEFAULTS)",
+            )
+            .execute(db_connection)?;
+
+            // addresses found directly in outputs win over the ones derived from p2pk outputs
+            let address_count = diesel::sql_query(
+                "INSERT INTO utxo_addresses_new (address, script_type) \
+                 SELECT DISTINCT ON (address) address, script_type \
+                 FROM utxo_address_staging \
+                 ORDER BY address, script_type NULLS FIRST",
+            )
+            .execute(db_connection)?;
+
+            // the primary key is built once after the load
+            diesel::sql_query(
+                "ALTER TABLE utxo_addresses_new \
+                 ADD CONSTRAINT utxo_addresses_new_pkey PRIMARY KEY (address)",
+            )
+            .execute(db_connection)?;
+
+            diesel::sql_query("DROP TABLE utxo_addresses").execute(db_connection)?;
+
+            diesel::sql_query("ALTER TABLE utxo_addresses_new RENAME TO utxo_addresses")
+                .execute(db_connection)?;
+
+            diesel::sql_query("ALTER INDEX utxo_addresses_new_pkey RENAME TO utxo_addresses_pkey")
+                .execute(db_connection)?;
+
+            diesel::sql_query("TRUNCATE utxo_address_staging").execute(db_connection)?;
+
+            Ok(address_count)
+        })
+    }
+
+    pub fn insert_found_address(
+        &self,
+        mut found_address: FoundAddress,
+    ) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        if let Some(encryptor) = &self.encryptor {
+            // ciphertexts differ on every encryption, the unique index can't skip known addresses
+            let count = found_addresses::table
+                .filter(found_addresses::address.eq(&found_address.address))
+                .filter(found_addresses::derivation_path.eq(&found_address.derivation_path))
+                .count()
+                .get_result::<i64>(&mut connection)?;
+
+            if count > 0 {
+                return Ok(0);
+            }
+
+            let ciphertext = encryptor.encrypt(&found_address.mnemonic)?;
+            found_address.mnemonic.zeroize();
+            found_address.mnemonic = ciphertext;
+        }
+
+        if found_address.source.is_empty() {
+            found_address.source = self.source.clone();
+        }
+
         let result = diesel::insert_into(found_addresses::table)
-            .values(found_address)
+            .values((
+                found_addresses::address.eq(&found_address.address),
+                found_addresses::derivation_path.eq(&found_address.derivation_path),
+                found_addresses::mnemonic.eq(&found_address.mnemonic),
+                found_addresses::script_type.eq(&found_address.script_type),
+                found_addresses::source.eq(&found_address.source),
+            ))
             .on_conflict((
                 found_addresses::address,
                 found_addresses::derivation_path,
@@ -83,6 +507,122 @@ impl Repo {
         Ok(result)
     }
 
+    pub fn found_addresses(
+        &self,
+        unacknowledged_only: bool,
+    ) -> Result<Vec<FoundAddressRecord>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let mut query = found_addresses::table.into_boxed();
+
+        if unacknowledged_only {
+            query = query.filter(found_addresses::acknowledged_at.is_null());
+        }
+
+        let found_addresses = query
+            .order(found_addresses::id)
+            .load::<FoundAddressRecord>(&mut connection)?;
+
+        Ok(found_addresses)
+    }
+
+    pub fn found_address(&self, id: i64) -> Result<Option<FoundAddressRecord>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let found_address = found_addresses::table
+            .find(id)
+            .first::<FoundAddressRecord>(&mut connection)
+            .optional()?;
+
+        Ok(found_address)
+    }
+
+    /// Marks found addresses as handled, returns the number of newly acknowledged ones
+    pub fn acknowledge_found_addresses(&self, ids: &[i64]) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let result = diesel::update(found_addresses::table)
+            .filter(found_addresses::id.eq_any(ids))
+            .filter(found_addresses::acknowledged_at.is_null())
+            .set(found_addresses::acknowledged_at.eq(SystemTime::now()))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    pub fn upsert_shard_progress(&self, progress: &ShardProgress) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let result = diesel::insert_into(shard_progress::table)
+            .values(progress)
+            .on_conflict((
+                shard_progress::search,
+                shard_progress::shard_index,
+                shard_progress::shard_count,
+            ))
+            .do_update()
+            .set((
+                shard_progress::checked.eq(progress.checked),
+                shard_progress::total.eq(progress.total),
+                shard_progress::updated_at.eq(progress.updated_at),
+            ))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    pub fn shard_progress(&self, search: &str) -> Result<Vec<ShardProgress>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let progress = shard_progress::table
+            .filter(shard_progress::search.eq(search))
+            .order((shard_progress::shard_count, shard_progress::shard_index))
+            .load::<ShardProgress>(&mut connection)?;
+
+        Ok(progress)
+    }
+
+    /// Records a failed block, the number of attempts is increased if it already failed before
+    pub fn upsert_failed_block(&self, block_number: i32, error: &str) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let result = diesel::insert_into(failed_blocks::table)
+            .values((
+                failed_blocks::block_number.eq(block_number),
+                failed_blocks::error.eq(error),
+            ))
+            .on_conflict(failed_blocks::block_number)
+            .do_update()
+            .set((
+                failed_blocks::error.eq(error),
+                failed_blocks::attempts.eq(failed_blocks::attempts + 1),
+                failed_blocks::failed_at.eq(SystemTime::now()),
+            ))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    pub fn failed_blocks(&self) -> Result<Vec<FailedBlock>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let failed_blocks = failed_blocks::table
+            .order(failed_blocks::block_number)
+            .load::<FailedBlock>(&mut connection)?;
+
+        Ok(failed_blocks)
+    }
+
+    pub fn delete_failed_block(&self, block_number: i32) -> Result<usize, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let result =
+            diesel::delete(failed_blocks::table.find(block_number)).execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    /// Whether the block is indexed or staged by bulk indexing, staged blocks are indexed once merged
     pub fn block_exists(&self, block_number: i32) -> Result<bool, RepoError> {
         let mut connection = self.pool().get()?;
 
@@ -91,16 +631,122 @@ impl Repo {
             .count()
             .get_result::<i64>(&mut connection)?;
 
-        Ok(count > 0)
+        if count > 0 {
+            return Ok(true);
+        }
+
+        let staged_count = block_staging::table
+            .filter(block_staging::block_number.eq(block_number))
+            .count()
+            .get_result::<i64>(&mut connection)?;
+
+        Ok(staged_count > 0)
+    }
+
+    /// Consecutive ranges of indexed blocks, ordered by height
+    pub fn indexed_block_ranges(&self) -> Result<Vec<RangeInclusive<u64>>, RepoError> {
+        self.block_ranges("SELECT block_number FROM blocks")
+    }
+
+    /// Consecutive ranges of indexed and staged blocks, ordered by height
+    pub fn indexed_or_staged_block_ranges(&self) -> Result<Vec<RangeInclusive<u64>>, RepoError> {
+        self.block_ranges(
+            "SELECT block_number FROM blocks UNION SELECT block_number FROM block_staging",
+        )
+    }
+
+    /// Consecutive ranges of the distinct block numbers `block_numbers` selects
+    fn block_ranges(&self, block_numbers: &str) -> Result<Vec<RangeInclusive<u64>>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let ranges = diesel::sql_query(format!(
+            "SELECT MIN(block_number) AS first, MAX(block_number) AS last \
+             FROM (SELECT block_number, block_number - ROW_NUMBER() OVER (ORDER BY block_number) AS island \
+                   FROM ({block_numbers}) AS block_numbers) AS numbered_blocks \
+             GROUP BY island \
+             ORDER BY first"
+        ))
+        .load::<BlockRange>(&mut connection)?;
+
+        let ranges = ranges
+            .into_iter()
+            .map(|range| range.first as u64..=range.last as u64)
+            .collect();
+
+        Ok(ranges)
+    }
+
+    /// Row count of the addresses table from the planner statistics, counting a billion rows takes minutes
+    pub fn estimated_address_count(&self) -> Result<u64, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let row_estimate = diesel::sql_query(
+            "SELECT reltuples::BIGINT AS count FROM pg_class WHERE relname = 'addresses'",
+        )
+        .get_result::<RowCount>(&mut connection)
+        .optional()?;
+
+        // reltuples is -1 for tables that were never analyzed
+        let estimate = row_estimate.map_or(0, |row_estimate| row_estimate.count.max(0));
+
+        Ok(estimate as u64)
+    }
+
+    /// Page of indexed addresses with their script types ordered by address, starting after `after`
+    pub fn addresses_after(
+        &self,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, Option<String>)>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let mut query = addresses::table
+            .select((addresses::address, addresses::script_type))
+            .into_boxed();
+
+        if let Some(after) = after {
+            query = query.filter(addresses::address.gt(after));
+        }
+
+        let addresses = query
+            .order(addresses::address)
+            .limit(limit)
+            .load::<(String, Option<String>)>(&mut connection)?;
+
+        Ok(addresses)
+    }
+
+    /// Addresses of `addresses` that are in the index, looked up in one query
+    pub fn existing_addresses(&self, addresses: &[String]) -> Result<HashSet<String>, RepoError> {
+        let mut connection = self.pool().get()?;
+
+        let existing_addresses = match self.address_set {
+            AddressSet::Used => addresses::table
+                .select(addresses::address)
+                .filter(addresses::address.eq_any(addresses))
+                .load::<String>(&mut connection)?,
+            AddressSet::Unspent => utxo_addresses::table
+                .select(utxo_addresses::address)
+                .filter(utxo_addresses::address.eq_any(addresses))
+                .load::<String>(&mut connection)?,
+        };
+
+        Ok(existing_addresses.into_iter().collect())
     }
 
     pub fn address_exists(&self, address: &str) -> Result<bool, RepoError> {
         let mut connection = self.pool().get()?;
 
-        let count = addresses::table
-            .filter(addresses::address.eq(address))
-            .count()
-            .get_result::<i64>(&mut connection)?;
+        let count = match self.address_set {
+            AddressSet::Used => addresses::table
+                .filter(addresses::address.eq(address))
+                .count()
+                .get_result::<i64>(&mut connection)?,
+            AddressSet::Unspent => utxo_addresses::table
+                .filter(utxo_addresses::address.eq(address))
+                .count()
+                .get_result::<i64>(&mut connection)?,
+        };
 
         Ok(count > 0)
     }
@@ -119,3 +765,17 @@ impl Repo {
         POOL.get_or_init(|| self.create_connection_pool())
     }
 }
+
+fn serialize_time<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_time(time))
+}
+
+fn serialize_optional_time<S: Serializer>(
+    time: &Option<SystemTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serialize_time(time, serializer),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,260 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+const COOKIE_FILE: &str = ".cookie";
+const CONFIG_FILE: &str = "bitcoin.conf";
+/// `bitcoin.conf` section of mainnet, settings outside of sections apply to it too
+const MAIN_SECTION: &str = "main";
+
+#[derive(Error, Debug)]
+pub enum RpcAuthError {
+    #[error("IO Error {path:?} - {error:?}")]
+    IoError { path: PathBuf, error: io::Error },
+    #[error("Invalid cookie file {0:?}, expected user:password")]
+    InvalidCookie(PathBuf),
+    #[error("No .cookie file or rpcuser/rpcpassword in bitcoin.conf found in {0:?}")]
+    NoCredentials(PathBuf),
+    #[error("The use of '#' in rpcpassword is not allowed in {0:?}, it starts a comment")]
+    CommentInPassword(PathBuf),
+}
+
+/// Bitcoin Core RPC credentials sent with HTTP basic auth
+#[derive(Clone)]
+pub enum RpcAuth {
+    UserPassword {
+        user: String,
+        password: String,
+    },
+    /// The cookie is read on every request, bitcoind writes a new one when it restarts
+    CookieFile(PathBuf),
+}
+
+impl RpcAuth {
+    /// Uses the `.cookie` file of a mainnet datadir, or `rpcuser`/`rpcpassword` from its
+    /// `bitcoin.conf`.
+    ///
+    /// The indexer only follows mainnet, so network subdirectories like `testnet3` are not probed
+    /// and only settings outside of sections, in the `[main]` section or prefixed with `main.` are
+    /// used.
+    pub fn from_datadir(datadir: &Path) -> Result<Self, RpcAuthError> {
+        let cookie_file = datadir.join(COOKIE_FILE);
+
+        if cookie_file.exists() {
+            return Ok(RpcAuth::CookieFile(cookie_file));
+        }
+
+        let config_file = datadir.join(CONFIG_FILE);
+
+        if !config_file.exists() {
+            return Err(RpcAuthError::NoCredentials(datadir.to_path_buf()));
+        }
+
+        let config = Zeroizing::new(read(&config_file)?);
+
+        match parse_config(&config) {
+            Ok((Some(user), Some(password))) => Ok(RpcAuth::UserPassword { user, password }),
+            Ok(_) => Err(RpcAuthError::NoCredentials(datadir.to_path_buf())),
+            Err(ConfigError::CommentInPassword) => {
+                Err(RpcAuthError::CommentInPassword(config_file))
+            }
+        }
+    }
+
+    /// Value of the Authorization header
+    pub fn header_value(&self) -> Result<String, RpcAuthError> {
+        let credentials = match self {
+            RpcAuth::UserPassword { user, password } => {
+                Zeroizing::new(format!("{user}:{password}"))
+            }
+            RpcAuth::CookieFile(path) => {
+                let cookie = Zeroizing::new(read(path)?);
+
+                if !cookie.contains(':') {
+                    return Err(RpcAuthError::InvalidCookie(path.clone()));
+                }
+
+                Zeroizing::new(cookie.trim().to_string())
+            }
+        };
+
+        Ok(format!("Basic {}", STANDARD.encode(credentials.as_bytes())))
+    }
+}
+
+enum ConfigError {
+    CommentInPassword,
+}
+
+/// Reads the mainnet `rpcuser` and `rpcpassword` like Bitcoin Core, everything after `#` is a
+/// comment and a password line with a comment is rejected
+fn parse_config(config: &str) -> Result<(Option<String>, Option<String>), ConfigError> {
+    let mut user = None;
+    let mut password = None;
+    let mut section = MAIN_SECTION;
+
+    for line in config.lines() {
+        let (line, has_comment) = match line.split_once('#') {
+            Some((line, _comment)) => (line.trim(), true),
+            None => (line.trim(), false),
+        };
+
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name.trim();
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim();
+
+        if has_comment && key.contains("rpcpassword") {
+            return Err(ConfigError::CommentInPassword);
+        }
+
+        let key = match key.split_once('.') {
+            Some((network, key)) if network == MAIN_SECTION => key,
+            Some(_) => continue,
+            None if section == MAIN_SECTION => key,
+            None => continue,
+        };
+
+        match key {
+            "rpcuser" => user = Some(value.trim().to_string()),
+            "rpcpassword" => password = Some(value.trim().to_string()),
+            _ => (),
+        }
+    }
+
+    Ok((user, password))
+}
+
+fn read(path: &Path) -> Result<String, RpcAuthError> {
+    fs::read_to_string(path).map_err(|error| RpcAuthError::IoError {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn credentials(config: &str) -> (Option<String>, Option<String>) {
+        parse_config(config).ok().unwrap()
+    }
+
+    fn some(user: &str, password: &str) -> (Option<String>, Option<String>) {
+        (Some(user.to_string()), Some(password.to_string()))
+    }
+
+    /// Fresh datadir for a test
+    fn datadir(name: &str) -> PathBuf {
+        let datadir = env::temp_dir().join(format!("foogold-rpc-auth-{}-{name}", process::id()));
+
+        let _ = fs::remove_dir_all(&datadir);
+        fs::create_dir_all(&datadir).unwrap();
+
+        datadir
+    }
+
+    #[test]
+    fn reads_mainnet_credentials() {
+        assert_eq!(
+            credentials("server=1\nrpcuser = alice\nrpcpassword=secret\n"),
+            some("alice", "secret")
+        );
+        assert_eq!(
+            credentials("[test]\nrpcuser=bob\nrpcpassword=test\n[main]\nrpcuser=alice\nrpcpassword=secret\n"),
+            some("alice", "secret")
+        );
+        assert_eq!(
+            credentials("rpcuser=alice\ntest.rpcpassword=test\nmain.rpcpassword=secret\n[regtest]\nrpcpassword=regtest\n"),
+            some("alice", "secret")
+        );
+        assert_eq!(
+            credentials("[test]\nrpcuser=bob\nrpcpassword=test\n"),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn strips_comments() {
+        assert_eq!(
+            credentials("# rpcuser=bob\nrpcuser=alice # the admin\nrpcpassword=secret\n#[test]\n"),
+            some("alice", "secret")
+        );
+        assert_eq!(
+            credentials("[test] # testnet\nrpcuser=bob\n[main]#\nrpcuser=alice\n"),
+            (Some("alice".to_string()), None)
+        );
+        assert!(matches!(
+            parse_config("rpcuser=alice\nrpcpassword=sec#ret\n"),
+            Err(ConfigError::CommentInPassword)
+        ));
+    }
+
+    #[test]
+    fn prefers_cookie_file() {
+        let datadir = datadir("cookie");
+        fs::write(
+            datadir.join(CONFIG_FILE),
+            "rpcuser=alice\nrpcpassword=secret\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            RpcAuth::from_datadir(&datadir).unwrap(),
+            RpcAuth::UserPassword { user, password } if user == "alice" && password == "secret"
+        ));
+
+        fs::write(datadir.join(COOKIE_FILE), "__cookie__:f00d\n").unwrap();
+        let auth = RpcAuth::from_datadir(&datadir).unwrap();
+
+        // "__cookie__:f00d" in base64
+        assert_eq!(auth.header_value().unwrap(), "Basic X19jb29raWVfXzpmMDBk");
+
+        // a restarted node writes a new cookie
+        fs::write(datadir.join(COOKIE_FILE), "__cookie__:beef").unwrap();
+        assert_eq!(auth.header_value().unwrap(), "Basic X19jb29raWVfXzpiZWVm");
+
+        fs::write(datadir.join(COOKIE_FILE), "f00d").unwrap();
+        assert!(matches!(
+            auth.header_value(),
+            Err(RpcAuthError::InvalidCookie(_))
+        ));
+
+        fs::remove_dir_all(datadir).unwrap();
+    }
+
+    #[test]
+    fn fails_without_credentials() {
+        let datadir = datadir("empty");
+
+        assert!(matches!(
+            RpcAuth::from_datadir(&datadir),
+            Err(RpcAuthError::NoCredentials(_))
+        ));
+
+        fs::write(datadir.join(CONFIG_FILE), "rpcpassword=secret # comment\n").unwrap();
+
+        assert!(matches!(
+            RpcAuth::from_datadir(&datadir),
+            Err(RpcAuthError::CommentInPassword(_))
+        ));
+
+        fs::remove_dir_all(datadir).unwrap();
+    }
+}
@@ -0,0 +1,516 @@
+use super::client::p2pk_script_to_p2pkh;
+use super::repo::StagedUtxoAddress;
+use super::Repo;
+use super::RepoError;
+use bitcoin::blockdata::script::Script;
+use bitcoin::hash_types::PubkeyHash;
+use bitcoin::hash_types::ScriptHash;
+use bitcoin::hashes::Hash;
+use bitcoin::network::constants::Network;
+use bitcoin::util::address::Address;
+use bitcoin::util::key::PublicKey;
+use bitcoin::BlockHash;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use thiserror::Error;
+
+/// Start of snapshots written by Bitcoin Core 28 and later
+const MAGIC_BYTES: [u8; 5] = *b"utxo\xff";
+const MAINNET_MAGIC: [u8; 4] = [0xf9, 0xbe, 0xb4, 0xd9];
+/// Number of compressed script types with a fixed size, see `ScriptCompression` of Bitcoin Core
+const SPECIAL_SCRIPT_COUNT: u64 = 6;
+const MAX_SCRIPT_SIZE: u64 = 10_000;
+/// Number of addresses copied into the staging table at once
+const COPY_CHUNK_SIZE: usize = 100_000;
+
+#[derive(Error, Debug)]
+pub enum UtxoSnapshotError {
+    #[error("IO Error {0:?}")]
+    IoError(#[from] io::Error),
+    #[error("Unsupported snapshot version {0}")]
+    UnsupportedVersion(u16),
+    #[error("Snapshot of network {0:02x?} instead of mainnet")]
+    UnsupportedNetwork([u8; 4]),
+    #[error("Invalid public key in a P2PK output")]
+    InvalidPublicKey,
+    #[error("Repo Error {0:?}")]
+    RepoError(#[from] RepoError),
+}
+
+/// Replaces the UTXO addresses of the index with the addresses of a snapshot file, returns the
+/// number of distinct addresses
+pub fn import_utxo_snapshot(repo: &Repo, path: &Path) -> Result<usize, UtxoSnapshotError> {
+    let snapshot = UtxoSnapshot::open(path)?;
+    let coin_count = snapshot.coin_count;
+
+    log::info!(
+        "Importing {coin_count} coins of the UTXO snapshot at block {}",
+        snapshot.base_block_hash
+    );
+
+    repo.clear_staged_utxo_addresses()?;
+
+    let mut chunk = Vec::with_capacity(COPY_CHUNK_SIZE);
+    let mut read_coins: u64 = 0;
+
+    for coin in snapshot {
+        read_coins += 1;
+
+        if let Some((address, script_type)) = coin?.address() {
+            chunk.push(StagedUtxoAddress {
+                address,
+                script_type: script_type.map(String::from),
+            });
+        }
+
+        if chunk.len() == COPY_CHUNK_SIZE {
+            repo.copy_utxo_addresses(&chunk)?;
+            chunk.clear();
+
+            log::info!("Read {read_coins} of {coin_count} coins");
+        }
+    }
+
+    repo.copy_utxo_addresses(&chunk)?;
+
+    log::info!("Read {read_coins} coins, replacing the UTXO addresses");
+
+    Ok(repo.replace_utxo_addresses()?)
+}
+
+/// Unspent output of a UTXO snapshot
+#[derive(Debug, Clone)]
+pub struct UtxoCoin {
+    pub height: u32,
+    pub is_coinbase: bool,
+    pub script_pub_key: Script,
+}
+
+impl UtxoCoin {
+    /// Address of the output and its script type if it's not encoded in the output directly.
+    ///
+    /// P2PK outputs are returned as the P2PKH address of their public key, like in the chain index.
+    pub fn address(&self) -> Option<(String, Option<&'static str>)> {
+        if self.script_pub_key.is_p2pk() {
+            return p2pk_script_to_p2pkh(&self.script_pub_key)
+                .map(|address| (address, Some("p2pk")));
+        }
+
+        Address::from_script(&self.script_pub_key, Network::Bitcoin)
+            .ok()
+            .map(|address| (address.to_string(), None))
+    }
+}
+
+/// Reads a snapshot written by the `dumptxoutset` RPC of Bitcoin Core without a node.
+///
+/// Both the format of Bitcoin Core 28+ (coins grouped by transaction) and the earlier one (an
+/// outpoint for every coin) are supported.
+pub struct UtxoSnapshot<R: Read> {
+    reader: R,
+    pub base_block_hash: BlockHash,
+    pub coin_count: u64,
+    grouped: bool,
+    coins_left: u64,
+    /// Coins left in the current transaction of grouped snapshots
+    transaction_coins_left: u64,
+}
+
+impl UtxoSnapshot<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, UtxoSnapshotError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> UtxoSnapshot<R> {
+    pub fn new(mut reader: R) -> Result<Self, UtxoSnapshotError> {
+        let mut start = [0; 5];
+        reader.read_exact(&mut start)?;
+
+        let grouped = start == MAGIC_BYTES;
+
+        let base_block_hash = if grouped {
+            let version = u16::from_le_bytes(read_array(&mut reader)?);
+
+            if version != 2 {
+                return Err(UtxoSnapshotError::UnsupportedVersion(version));
+            }
+
+            let network_magic: [u8; 4] = read_array(&mut reader)?;
+
+            if network_magic != MAINNET_MAGIC {
+                return Err(UtxoSnapshotError::UnsupportedNetwork(network_magic));
+            }
+
+            BlockHash::from_inner(read_array(&mut reader)?)
+        } else {
+            // older snapshots start with the block hash right away
+            let mut block_hash = [0; 32];
+            block_hash[..5].copy_from_slice(&start);
+            reader.read_exact(&mut block_hash[5..])?;
+
+            BlockHash::from_inner(block_hash)
+        };
+
+        let coin_count = u64::from_le_bytes(read_array(&mut reader)?);
+
+        Ok(Self {
+            reader,
+            base_block_hash,
+            coin_count,
+            grouped,
+            coins_left: coin_count,
+            transaction_coins_left: 0,
+        })
+    }
+
+    fn read_coin(&mut self) -> Result<UtxoCoin, UtxoSnapshotError> {
+        if self.grouped {
+            if self.transaction_coins_left == 0 {
+                let _txid: [u8; 32] = read_array(&mut self.reader)?;
+                self.transaction_coins_left = read_compact_size(&mut self.reader)?;
+            }
+
+            let _vout = read_compact_size(&mut self.reader)?;
+            self.transaction_coins_left = self.transaction_coins_left.saturating_sub(1);
+        } else {
+            let _outpoint: [u8; 36] = read_array(&mut self.reader)?;
+        }
+
+        let code = read_var_int(&mut self.reader)?;
+        let _compressed_amount = read_var_int(&mut self.reader)?;
+        let script_pub_key = self.read_compressed_script()?;
+
+        Ok(UtxoCoin {
+            height: (code >> 1) as u32,
+            is_coinbase: code & 1 == 1,
+            script_pub_key,
+        })
+    }
+
+    fn read_compressed_script(&mut self) -> Result<Script, UtxoSnapshotError> {
+        let size = read_var_int(&mut self.reader)?;
+
+        let script = match size {
+            0 => Script::new_p2pkh(&PubkeyHash::from_inner(read_array(&mut self.reader)?)),
+            1 => Script::new_p2sh(&ScriptHash::from_inner(read_array(&mut self.reader)?)),
+            2..=5 => {
+                let x: [u8; 32] = read_array(&mut self.reader)?;
+                let prefix = if size < 4 { size } else { size - 2 };
+
+                let mut compressed_key = [0; 33];
+                compressed_key[0] = prefix as u8;
+                compressed_key[1..].copy_from_slice(&x);
+
+                let mut public_key = PublicKey::from_slice(&compressed_key)
+                    .map_err(|_| UtxoSnapshotError::InvalidPublicKey)?;
+
+                // 4 and 5 are uncompressed keys stored as their compressed form
+                public_key.compressed = size < 4;
+
+                Script::new_p2pk(&public_key)
+            }
+            _ => {
+                let size = size - SPECIAL_SCRIPT_COUNT;
+
+                if size > MAX_SCRIPT_SIZE {
+                    // oversized scripts are unspendable, Bitcoin Core keeps them as OP_RETURN
+                    io::copy(&mut (&mut self.reader).take(size), &mut io::sink())?;
+
+                    return Ok(Script::new_op_return(&[]));
+                }
+
+                let mut bytes = vec![0; size as usize];
+                self.reader.read_exact(&mut bytes)?;
+
+                Script::from(bytes)
+            }
+        };
+
+        Ok(script)
+    }
+}
+
+impl<R: Read> Iterator for UtxoSnapshot<R> {
+    type Item = Result<UtxoCoin, UtxoSnapshotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.coins_left == 0 {
+            return None;
+        }
+
+        self.coins_left -= 1;
+
+        let coin = self.read_coin();
+
+        if coin.is_err() {
+            self.coins_left = 0;
+        }
+
+        Some(coin)
+    }
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], io::Error> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Bitcoin Core's `VARINT`, an MSB base-128 encoding that is different from CompactSize
+fn read_var_int<R: Read>(reader: &mut R) -> Result<u64, io::Error> {
+    let mut n: u64 = 0;
+
+    loop {
+        let [byte] = read_array(reader)?;
+
+        n = (n << 7) | u64::from(byte & 0x7f);
+
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+
+        n += 1;
+    }
+}
+
+fn read_compact_size<R: Read>(reader: &mut R) -> Result<u64, io::Error> {
+    let [first] = read_array(reader)?;
+
+    let size = match first {
+        0xfd => u64::from(u16::from_le_bytes(read_array(reader)?)),
+        0xfe => u64::from(u32::from_le_bytes(read_array(reader)?)),
+        0xff => u64::from_le_bytes(read_array(reader)?),
+        _ => u64::from(first),
+    };
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::hex::FromHex;
+
+    /// x coordinate of the secp256k1 generator, the public key of the private key 1
+    const GENERATOR_X: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    /// HASH160 of the compressed generator
+    const GENERATOR_HASH: &str = "751e76e8199196d454941c45d1b3a323f1433bd6";
+    const BLOCK_HASH: [u8; 32] = [7; 32];
+
+    /// Bitcoin Core's `VARINT`
+    fn var_int(mut n: u64) -> Vec<u8> {
+        let mut bytes = vec![(n & 0x7f) as u8];
+
+        while n > 0x7f {
+            n = (n >> 7) - 1;
+            bytes.push((n & 0x7f) as u8 | 0x80);
+        }
+
+        bytes.reverse();
+        bytes
+    }
+
+    /// Coin with the height 100 and a zero amount, the script is already compressed
+    fn coin(compressed_script: &[u8]) -> Vec<u8> {
+        [
+            var_int(100 << 1 | 1),
+            var_int(0),
+            compressed_script.to_vec(),
+        ]
+        .concat()
+    }
+
+    fn special_script(size: u8, payload: &str) -> Vec<u8> {
+        [vec![size], Vec::from_hex(payload).unwrap()].concat()
+    }
+
+    /// P2PKH, P2SH, P2PK with a compressed and two uncompressed keys, P2WPKH as a raw script, an
+    /// oversized script and P2PKH again
+    fn coins() -> Vec<Vec<u8>> {
+        let p2wpkh = Vec::from_hex(&format!("0014{GENERATOR_HASH}")).unwrap();
+        let oversized_size = MAX_SCRIPT_SIZE + 1;
+
+        vec![
+            coin(&special_script(0, GENERATOR_HASH)),
+            coin(&special_script(1, GENERATOR_HASH)),
+            coin(&special_script(2, GENERATOR_X)),
+            coin(&special_script(4, GENERATOR_X)),
+            coin(&special_script(5, GENERATOR_X)),
+            coin(&[var_int(p2wpkh.len() as u64 + SPECIAL_SCRIPT_COUNT), p2wpkh].concat()),
+            coin(
+                &[
+                    var_int(oversized_size + SPECIAL_SCRIPT_COUNT),
+                    vec![0x51; oversized_size as usize],
+                ]
+                .concat(),
+            ),
+            coin(&special_script(0, GENERATOR_HASH)),
+        ]
+    }
+
+    /// Format of Bitcoin Core 28+, the first three coins belong to one transaction
+    fn grouped_snapshot(coins: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = [
+            MAGIC_BYTES.to_vec(),
+            2u16.to_le_bytes().to_vec(),
+            MAINNET_MAGIC.to_vec(),
+            BLOCK_HASH.to_vec(),
+            (coins.len() as u64).to_le_bytes().to_vec(),
+        ]
+        .concat();
+
+        let (first, rest) = coins.split_at(3);
+
+        for (txid, transaction) in [[1; 32], [2; 32]].iter().zip([first, rest]) {
+            bytes.extend(txid);
+            bytes.push(transaction.len() as u8);
+
+            for (vout, coin) in transaction.iter().enumerate() {
+                bytes.push(vout as u8);
+                bytes.extend(coin);
+            }
+        }
+
+        bytes
+    }
+
+    /// Format before Bitcoin Core 28, every coin has its outpoint
+    fn legacy_snapshot(coins: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = [
+            BLOCK_HASH.to_vec(),
+            (coins.len() as u64).to_le_bytes().to_vec(),
+        ]
+        .concat();
+
+        for coin in coins {
+            bytes.extend([3; 36]);
+            bytes.extend(coin);
+        }
+
+        bytes
+    }
+
+    fn addresses(snapshot: &[u8]) -> Vec<Option<(String, Option<&'static str>)>> {
+        let snapshot = UtxoSnapshot::new(snapshot).unwrap();
+
+        assert_eq!(snapshot.base_block_hash, BlockHash::from_inner(BLOCK_HASH));
+        assert_eq!(snapshot.coin_count, 8);
+
+        snapshot
+            .map(|coin| {
+                let coin = coin.unwrap();
+
+                assert_eq!(coin.height, 100);
+                assert!(coin.is_coinbase);
+
+                coin.address()
+            })
+            .collect()
+    }
+
+    fn expected_addresses() -> Vec<Option<(String, Option<&'static str>)>> {
+        let mut odd_key =
+            PublicKey::from_slice(&Vec::from_hex(&format!("03{GENERATOR_X}")).unwrap()).unwrap();
+        odd_key.compressed = false;
+
+        let address = |address: &str, script_type| Some((address.to_string(), script_type));
+
+        vec![
+            address("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", None),
+            address("3CNHUhP3uyB9EUtRLsmvFUmvGdjGdkTxJw", None),
+            address("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", Some("p2pk")),
+            address("1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm", Some("p2pk")),
+            address(
+                &Address::p2pkh(&odd_key, Network::Bitcoin).to_string(),
+                Some("p2pk"),
+            ),
+            address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", None),
+            None,
+            address("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", None),
+        ]
+    }
+
+    #[test]
+    fn var_int_matches_bitcoin_core_examples() {
+        let examples: [(u64, &[u8]); 7] = [
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x00]),
+            (255, &[0x80, 0x7f]),
+            (16511, &[0xff, 0x7f]),
+            (65535, &[0x82, 0xfe, 0x7f]),
+            (1 << 32, &[0x8e, 0xfe, 0xfe, 0xff, 0x00]),
+        ];
+
+        for (n, bytes) in examples {
+            assert_eq!(var_int(n), bytes);
+            assert_eq!(read_var_int(&mut &bytes[..]).unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn reads_grouped_snapshot() {
+        assert_eq!(addresses(&grouped_snapshot(&coins())), expected_addresses());
+    }
+
+    #[test]
+    fn reads_legacy_snapshot() {
+        assert_eq!(addresses(&legacy_snapshot(&coins())), expected_addresses());
+    }
+
+    #[test]
+    fn decompresses_uncompressed_public_keys() {
+        let snapshot = legacy_snapshot(&coins());
+        let scripts: Vec<Script> = UtxoSnapshot::new(snapshot.as_slice())
+            .unwrap()
+            .map(|coin| coin.unwrap().script_pub_key)
+            .collect();
+
+        for script in &scripts[3..5] {
+            assert!(script.is_p2pk());
+            assert_eq!(script.len(), 67);
+            assert_eq!(script[1], 0x04);
+        }
+
+        // nSize 4 has an even y, 5 an odd one
+        assert_eq!(scripts[3][65] & 1, 0);
+        assert_eq!(scripts[4][65] & 1, 1);
+        assert!(scripts[6].is_op_return());
+    }
+
+    #[test]
+    fn rejects_other_networks_and_versions() {
+        let mut snapshot = grouped_snapshot(&coins());
+        snapshot[7..11].copy_from_slice(&[0x0b, 0x11, 0x09, 0x07]);
+
+        assert!(matches!(
+            UtxoSnapshot::new(snapshot.as_slice()),
+            Err(UtxoSnapshotError::UnsupportedNetwork(_))
+        ));
+
+        let mut snapshot = grouped_snapshot(&coins());
+        snapshot[5] = 3;
+
+        assert!(matches!(
+            UtxoSnapshot::new(snapshot.as_slice()),
+            Err(UtxoSnapshotError::UnsupportedVersion(3))
+        ));
+    }
+
+    #[test]
+    fn stops_at_truncated_coin() {
+        let mut snapshot = legacy_snapshot(&coins());
+        snapshot.truncate(snapshot.len() - 5);
+
+        let coins: Vec<_> = UtxoSnapshot::new(snapshot.as_slice()).unwrap().collect();
+
+        assert_eq!(coins.len(), 8);
+        assert!(coins[..7].iter().all(Result::is_ok));
+        assert!(matches!(coins[7], Err(UtxoSnapshotError::IoError(_))));
+    }
+}
@@ -2,13 +2,60 @@ pub mod checker;
 mod indexer;
 mod schema;
 
+pub use checker::AccountKey;
+pub use checker::AccountKeyError;
 pub use checker::AddressGenerator;
+pub use checker::Aezeed;
+pub use checker::AezeedChecker;
+pub use checker::Audit;
 pub use checker::CombinationChecker;
+pub use checker::Descriptor;
+pub use checker::EntropyPattern;
+pub use checker::FileNotifier;
+pub use checker::FoundOutput;
+pub use checker::MatrixNotifier;
 pub use checker::MnemonicChecker;
 pub use checker::MnemonicGenerator;
+pub use checker::MnemonicInput;
+pub use checker::Multisig;
+pub use checker::MultisigError;
+pub use checker::MultisigScriptType;
+pub use checker::Notification;
+pub use checker::NotificationKind;
+pub use checker::NotificationQueue;
+pub use checker::Notifier;
+pub use checker::Notifiers;
 pub use checker::RandomChecker;
+pub use checker::ScriptType;
+pub use checker::Shard;
+pub use checker::ShardError;
+pub use checker::Slip39Checker;
+pub use checker::Slip39Share;
+pub use checker::Slip39Wordlist;
+pub use checker::SmtpNotifier;
+pub use checker::Target;
 pub use checker::TelegramClient;
+pub use checker::WebhookNotifier;
+pub use indexer::export_index;
+pub use indexer::format_time;
+pub use indexer::import_index;
+pub use indexer::import_utxo_snapshot;
+pub use indexer::is_encrypted;
+pub use indexer::render_found_addresses;
+pub use indexer::verify_index_file;
+pub use indexer::AddressSet;
+pub use indexer::FailedBlock;
 pub use indexer::FoundAddress;
+pub use indexer::FoundAddressRecord;
+pub use indexer::IndexFileHeader;
+pub use indexer::IndexStatus;
 pub use indexer::Indexer;
+pub use indexer::LookupServer;
+pub use indexer::OutputFormat;
 pub use indexer::Repo;
+pub use indexer::RpcAuth;
 pub use indexer::RpcClient;
+pub use indexer::SecretDecryptor;
+pub use indexer::SecretEncryptor;
+pub use indexer::ShardProgress;
+pub use indexer::UtxoSnapshot;
@@ -3,6 +3,7 @@
 diesel::table! {
     addresses (address) {
         address -> Text,
+        script_type -> Nullable<Text>,
     }
 }
 
@@ -12,17 +13,51 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    failed_blocks (block_number) {
+        block_number -> Int4,
+        error -> Text,
+        attempts -> Int4,
+        failed_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     found_addresses (id) {
         id -> Int8,
-        address -> Nullable<Text>,
-        derivation_path -> Nullable<Text>,
-        mnemonic -> Nullable<Text>,
+        address -> Text,
+        derivation_path -> Text,
+        mnemonic -> Text,
+        script_type -> Text,
+        found_at -> Timestamp,
+        source -> Text,
+        acknowledged_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    shard_progress (search, shard_index, shard_count) {
+        search -> Text,
+        shard_index -> Int4,
+        shard_count -> Int4,
+        checked -> Int8,
+        total -> Int8,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    utxo_addresses (address) {
+        address -> Text,
+        script_type -> Nullable<Text>,
     }
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
     addresses,
     blocks,
+    failed_blocks,
     found_addresses,
+    shard_progress,
+    utxo_addresses,
 );